@@ -3,6 +3,7 @@ use std::fmt;
 
 pub trait Node: fmt::Display + fmt::Debug {
     fn token_literal(&self) -> &str;
+    fn span(&self) -> token::Span;
 }
 
 // pub trait Statement: Node {}
@@ -11,8 +12,16 @@ pub trait Node: fmt::Display + fmt::Debug {
 pub enum Expression {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
+    StringLiteral(StringLiteral),
     Boolean(Boolean),
     PrefixExpression(PrefixExpression),
+    InfixExpression(InfixExpression),
+    IfExpression(IfExpression),
+    FunctionLiteral(FunctionLiteral),
+    CallExpression(CallExpression),
+    ArrayLiteral(ArrayLiteral),
+    IndexExpression(IndexExpression),
+    HashLiteral(HashLiteral),
 }
 
 impl fmt::Display for Expression {
@@ -23,16 +32,62 @@ impl fmt::Display for Expression {
             match self {
                 Expression::Identifier(expr) => expr.to_string(),
                 Expression::IntegerLiteral(integer) => integer.to_string(),
+                Expression::StringLiteral(string) => string.to_string(),
                 Expression::Boolean(boolean) => boolean.to_string(),
                 Expression::PrefixExpression(prefix_expression) => prefix_expression.to_string(),
+                Expression::InfixExpression(infix_expression) => infix_expression.to_string(),
+                Expression::IfExpression(if_expression) => if_expression.to_string(),
+                Expression::FunctionLiteral(function_literal) => function_literal.to_string(),
+                Expression::CallExpression(call_expression) => call_expression.to_string(),
+                Expression::ArrayLiteral(array_literal) => array_literal.to_string(),
+                Expression::IndexExpression(index_expression) => index_expression.to_string(),
+                Expression::HashLiteral(hash_literal) => hash_literal.to_string(),
             }
         )
     }
 }
 
-#[derive(Debug)]
+impl Node for Expression {
+    fn token_literal(&self) -> &str {
+        match self {
+            Expression::Identifier(expr) => expr.token_literal(),
+            Expression::IntegerLiteral(integer) => integer.token_literal(),
+            Expression::StringLiteral(string) => string.token_literal(),
+            Expression::Boolean(boolean) => boolean.token_literal(),
+            Expression::PrefixExpression(prefix_expression) => prefix_expression.token_literal(),
+            Expression::InfixExpression(infix_expression) => infix_expression.token_literal(),
+            Expression::IfExpression(if_expression) => if_expression.token_literal(),
+            Expression::FunctionLiteral(function_literal) => function_literal.token_literal(),
+            Expression::CallExpression(call_expression) => call_expression.token_literal(),
+            Expression::ArrayLiteral(array_literal) => array_literal.token_literal(),
+            Expression::IndexExpression(index_expression) => index_expression.token_literal(),
+            Expression::HashLiteral(hash_literal) => hash_literal.token_literal(),
+        }
+    }
+
+    fn span(&self) -> token::Span {
+        match self {
+            Expression::Identifier(expr) => expr.span(),
+            Expression::IntegerLiteral(integer) => integer.span(),
+            Expression::StringLiteral(string) => string.span(),
+            Expression::Boolean(boolean) => boolean.span(),
+            Expression::PrefixExpression(prefix_expression) => prefix_expression.span(),
+            Expression::InfixExpression(infix_expression) => infix_expression.span(),
+            Expression::IfExpression(if_expression) => if_expression.span(),
+            Expression::FunctionLiteral(function_literal) => function_literal.span(),
+            Expression::CallExpression(call_expression) => call_expression.span(),
+            Expression::ArrayLiteral(array_literal) => array_literal.span(),
+            Expression::IndexExpression(index_expression) => index_expression.span(),
+            Expression::HashLiteral(hash_literal) => hash_literal.span(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Statement {
     LetStmt(LetStmt),
+    AssignStatement(AssignStatement),
     ReturnStmt(ReturnStmt),
     ExpressionStmt(ExpressionStmt),
 }
@@ -44,6 +99,7 @@ impl fmt::Display for Statement {
             "{}",
             match self {
                 Statement::LetStmt(let_stmt) => let_stmt.to_string(),
+                Statement::AssignStatement(assign) => assign.to_string(),
                 Statement::ReturnStmt(return_stmt) => return_stmt.to_string(),
                 Statement::ExpressionStmt(expression_stmt) => expression_stmt.to_string(),
             }
@@ -51,6 +107,26 @@ impl fmt::Display for Statement {
     }
 }
 
+impl Node for Statement {
+    fn token_literal(&self) -> &str {
+        match self {
+            Statement::LetStmt(let_stmt) => let_stmt.token_literal(),
+            Statement::AssignStatement(assign) => assign.token_literal(),
+            Statement::ReturnStmt(return_stmt) => return_stmt.token_literal(),
+            Statement::ExpressionStmt(expression_stmt) => expression_stmt.token_literal(),
+        }
+    }
+
+    fn span(&self) -> token::Span {
+        match self {
+            Statement::LetStmt(let_stmt) => let_stmt.span(),
+            Statement::AssignStatement(assign) => assign.span(),
+            Statement::ReturnStmt(return_stmt) => return_stmt.span(),
+            Statement::ExpressionStmt(expression_stmt) => expression_stmt.span(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Identifier {
     pub token: token::Token,
@@ -58,13 +134,10 @@ pub struct Identifier {
 }
 
 impl Identifier {
-    pub fn new(value: &str) -> Identifier {
+    pub fn new(token: &token::Token) -> Identifier {
         Identifier {
-            token: token::Token {
-                token_type: token::TokenType::Identifier,
-                literal: value.to_string(),
-            },
-            value: value.to_string(),
+            token: token.clone(),
+            value: token.literal.clone(),
         }
     }
 }
@@ -76,7 +149,11 @@ impl fmt::Display for Identifier {
 }
 impl Node for Identifier {
     fn token_literal(&self) -> &str {
-        &self.value
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.token.span
     }
 }
 
@@ -87,12 +164,13 @@ pub struct IntegerLiteral {
 }
 
 impl IntegerLiteral {
-    pub fn new(token: &token::Token) -> IntegerLiteral {
-        let value = token.literal.parse::<i64>().unwrap();
-        IntegerLiteral {
+    /// Returns `None` when the literal does not fit in an `i64`.
+    pub fn new(token: &token::Token) -> Option<IntegerLiteral> {
+        let value = token.literal.parse::<i64>().ok()?;
+        Some(IntegerLiteral {
             token: token.clone(),
             value,
-        }
+        })
     }
 }
 
@@ -105,6 +183,54 @@ impl Node for IntegerLiteral {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.token.span
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringLiteral {
+    pub token: token::Token,
+    pub value: String,
+}
+
+impl StringLiteral {
+    pub fn new(token: &token::Token) -> StringLiteral {
+        StringLiteral {
+            token: token.clone(),
+            value: token.literal.clone(),
+        }
+    }
+}
+
+/// Renders the string as a Monkey literal, escaping characters that cannot
+/// appear verbatim between quotes.
+impl fmt::Display for StringLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for ch in self.value.chars() {
+            match ch {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\t' => write!(f, "\\t")?,
+                ch if ch.is_control() => write!(f, "\\u{{{:x}}}", u32::from(ch))?,
+                ch => write!(f, "{ch}")?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl Node for StringLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.token.span
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -135,13 +261,17 @@ impl Node for Boolean {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.token.span
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PrefixExpression {
-    token: token::Token,
-    operator: String,
-    right: Box<Expression>,
+    pub token: token::Token,
+    pub operator: String,
+    pub right: Box<Expression>,
 }
 
 impl PrefixExpression {
@@ -156,7 +286,7 @@ impl PrefixExpression {
 
 impl fmt::Display for PrefixExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}{})", self.operator, self.right.to_string())
+        write!(f, "({}{})", self.operator, self.right)
     }
 }
 
@@ -164,21 +294,353 @@ impl Node for PrefixExpression {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.token.span.to(self.right.span())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InfixExpression {
+    pub token: token::Token,
+    pub left: Box<Expression>,
+    pub operator: String,
+    pub right: Box<Expression>,
+}
+
+impl InfixExpression {
+    pub fn new(
+        token: token::Token,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    ) -> InfixExpression {
+        InfixExpression {
+            left,
+            right,
+            operator: token.literal.clone(),
+            token,
+        }
+    }
+}
+
+impl fmt::Display for InfixExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} {} {})", self.left, self.operator, self.right)
+    }
 }
 
-#[derive(Debug)]
+impl Node for InfixExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.left.span().to(self.right.span())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IfExpression {
+    pub token: token::Token,
+    pub condition: Box<Expression>,
+    pub consequence: Box<BlockStatement>,
+    pub alternative: Option<Box<BlockStatement>>,
+}
+
+impl IfExpression {
+    pub fn new(
+        token: token::Token,
+        condition: Box<Expression>,
+        consequence: BlockStatement,
+        alternative: Option<BlockStatement>,
+    ) -> IfExpression {
+        IfExpression {
+            token,
+            condition,
+            consequence: Box::new(consequence),
+            alternative: alternative.map(Box::new),
+        }
+    }
+}
+
+impl fmt::Display for IfExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "if {} {}", self.condition, self.consequence)?;
+        if let Some(alternative) = &self.alternative {
+            write!(f, " else {alternative}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Node for IfExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        let end = self
+            .alternative
+            .as_deref()
+            .unwrap_or(&self.consequence)
+            .span();
+        self.token.span.to(end)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionLiteral {
+    pub token: token::Token,
+    pub parameters: Vec<Identifier>,
+    pub body: Box<BlockStatement>,
+}
+
+impl FunctionLiteral {
+    pub fn new(
+        token: token::Token,
+        parameters: Vec<Identifier>,
+        body: BlockStatement,
+    ) -> FunctionLiteral {
+        FunctionLiteral {
+            token,
+            parameters,
+            body: Box::new(body),
+        }
+    }
+}
+
+impl fmt::Display for FunctionLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "{}({}) {}",
+            self.token.literal,
+            parameters.join(", "),
+            self.body
+        )
+    }
+}
+
+impl Node for FunctionLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.token.span.to(self.body.span)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CallExpression {
+    pub token: token::Token,
+    pub function: Box<Expression>,
+    pub arguments: Vec<Expression>,
+    pub span: token::Span,
+}
+
+impl CallExpression {
+    pub fn new(
+        token: token::Token,
+        function: Box<Expression>,
+        arguments: Vec<Expression>,
+        end: token::Span,
+    ) -> CallExpression {
+        CallExpression {
+            span: function.span().to(end),
+            token,
+            function,
+            arguments,
+        }
+    }
+}
+
+impl fmt::Display for CallExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arguments: Vec<String> = self.arguments.iter().map(ToString::to_string).collect();
+        write!(f, "{}({})", self.function, arguments.join(", "))
+    }
+}
+
+impl Node for CallExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrayLiteral {
+    pub token: token::Token,
+    pub elements: Vec<Expression>,
+    pub span: token::Span,
+}
+
+impl ArrayLiteral {
+    pub fn new(token: token::Token, elements: Vec<Expression>, end: token::Span) -> ArrayLiteral {
+        ArrayLiteral {
+            span: token.span.to(end),
+            token,
+            elements,
+        }
+    }
+}
+
+impl fmt::Display for ArrayLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(ToString::to_string).collect();
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+impl Node for ArrayLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexExpression {
+    pub token: token::Token,
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+    pub span: token::Span,
+}
+
+impl IndexExpression {
+    pub fn new(
+        token: token::Token,
+        left: Box<Expression>,
+        index: Box<Expression>,
+        end: token::Span,
+    ) -> IndexExpression {
+        IndexExpression {
+            span: left.span().to(end),
+            token,
+            left,
+            index,
+        }
+    }
+}
+
+impl fmt::Display for IndexExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+impl Node for IndexExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashLiteral {
+    pub token: token::Token,
+    pub pairs: Vec<(Expression, Expression)>,
+    pub span: token::Span,
+}
+
+impl HashLiteral {
+    pub fn new(
+        token: token::Token,
+        pairs: Vec<(Expression, Expression)>,
+        end: token::Span,
+    ) -> HashLiteral {
+        HashLiteral {
+            span: token.span.to(end),
+            token,
+            pairs,
+        }
+    }
+}
+
+impl fmt::Display for HashLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{key}: {value}"))
+            .collect();
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
+
+impl Node for HashLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockStatement {
+    pub token: token::Token,
+    pub statements: Vec<Statement>,
+    pub span: token::Span,
+}
+
+impl BlockStatement {
+    pub fn new(
+        token: token::Token,
+        statements: Vec<Statement>,
+        end: token::Span,
+    ) -> BlockStatement {
+        BlockStatement {
+            span: token.span.to(end),
+            token,
+            statements,
+        }
+    }
+}
+
+impl fmt::Display for BlockStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let statements: Vec<String> = self.statements.iter().map(ToString::to_string).collect();
+        if statements.is_empty() {
+            write!(f, "{{}}")
+        } else {
+            write!(f, "{{ {} }}", statements.join(" "))
+        }
+    }
+}
+
+impl Node for BlockStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct LetStmt {
     pub token: token::Token,
     pub name: Identifier,
-    pub value: Option<Expression>,
+    pub value: Expression,
 }
 impl LetStmt {
-    pub fn new(identifier: Identifier, value: Option<Expression>) -> LetStmt {
+    pub fn new(token: token::Token, identifier: Identifier, value: Expression) -> LetStmt {
         LetStmt {
-            token: token::Token {
-                token_type: token::TokenType::Let,
-                literal: "let".to_string(),
-            },
+            token,
             name: identifier,
             value,
         }
@@ -187,59 +649,93 @@ impl LetStmt {
 
 impl fmt::Display for LetStmt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} {} = {};",
-            self.token.literal,
-            self.name,
-            self.value
-                .as_ref()
-                .map_or(String::default(), std::string::ToString::to_string)
-        )
+        write!(f, "{} {} = {};", self.token.literal, self.name, self.value)
     }
 }
 impl Node for LetStmt {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.token.span.to(self.value.span())
+    }
+}
+
+/// Reassigns an existing binding, either plainly (`x = 1`) or through a
+/// compound operator (`x += 1`).
+#[derive(Debug, Clone)]
+pub struct AssignStatement {
+    pub token: token::Token,
+    pub name: Identifier,
+    pub operator: String,
+    pub value: Expression,
+}
+
+impl AssignStatement {
+    pub fn new(token: token::Token, name: Identifier, value: Expression) -> AssignStatement {
+        AssignStatement {
+            operator: token.literal.clone(),
+            token,
+            name,
+            value,
+        }
+    }
+
+    /// The infix operator a compound assignment applies, e.g. `+` for `+=`.
+    pub fn infix_operator(&self) -> Option<&str> {
+        self.operator
+            .strip_suffix('=')
+            .filter(|operator| !operator.is_empty())
+    }
+}
+
+impl fmt::Display for AssignStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {};", self.name, self.operator, self.value)
+    }
+}
+
+impl Node for AssignStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+
+    fn span(&self) -> token::Span {
+        self.name.span().to(self.value.span())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReturnStmt {
     pub token: token::Token,
-    pub return_value: Option<ExpressionStmt>,
+    pub return_value: Expression,
 }
 
 impl ReturnStmt {
-    pub fn new(return_value: Option<ExpressionStmt>) -> ReturnStmt {
+    pub fn new(token: token::Token, return_value: Expression) -> ReturnStmt {
         ReturnStmt {
-            token: token::Token {
-                token_type: token::TokenType::Return,
-                literal: "return".to_string(),
-            },
+            token,
             return_value,
         }
     }
 }
 impl fmt::Display for ReturnStmt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{} {};",
-            self.token_literal(),
-            self.return_value
-                .as_ref()
-                .map_or(String::default(), std::string::ToString::to_string)
-        )
+        write!(f, "{} {};", self.token_literal(), self.return_value)
     }
 }
 impl Node for ReturnStmt {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.token.span.to(self.return_value.span())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ExpressionStmt {
     pub token: token::Token,
     pub expression: Expression,
@@ -255,6 +751,10 @@ impl Node for ExpressionStmt {
     fn token_literal(&self) -> &str {
         &self.token.literal
     }
+
+    fn span(&self) -> token::Span {
+        self.expression.span()
+    }
 }
 
 #[derive(Default, Debug)]
@@ -276,6 +776,7 @@ impl Node for Program {
         if let Some(stmt) = self.statements.first() {
             match stmt {
                 Statement::LetStmt(let_stmt) => &let_stmt.token,
+                Statement::AssignStatement(assign) => &assign.token,
                 Statement::ReturnStmt(return_stmt) => &return_stmt.token,
                 Statement::ExpressionStmt(expression_stmt) => &expression_stmt.token,
             }
@@ -285,6 +786,13 @@ impl Node for Program {
             ""
         }
     }
+
+    fn span(&self) -> token::Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => token::Span::default(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -295,8 +803,12 @@ mod test {
     fn test_to_string() {
         let program = Program {
             statements: vec![Statement::LetStmt(LetStmt::new(
-                Identifier::new("myVar"),
-                Some(Expression::Identifier(Identifier::new("anotherVar"))),
+                token::Token::new(token::TokenType::Let, "let"),
+                Identifier::new(&token::Token::new(token::TokenType::Identifier, "myVar")),
+                Expression::Identifier(Identifier::new(&token::Token::new(
+                    token::TokenType::Identifier,
+                    "anotherVar",
+                ))),
             ))],
         };
         assert_eq!(program.to_string(), "let myVar = anotherVar;");
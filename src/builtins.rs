@@ -0,0 +1,136 @@
+use crate::object::{Builtin, Object};
+
+/// Every builtin, in a fixed order so that they can be referred to by index.
+pub const BUILTINS: &[Builtin] = &[
+    Builtin::new("len", len),
+    Builtin::new("puts", puts),
+    Builtin::new("first", first),
+    Builtin::new("last", last),
+    Builtin::new("rest", rest),
+    Builtin::new("push", push),
+];
+
+pub fn lookup(name: &str) -> Option<Builtin> {
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name == name)
+        .copied()
+}
+
+fn check_argument_count(arguments: &[Object], want: usize) -> Result<(), Object> {
+    if arguments.len() == want {
+        Ok(())
+    } else {
+        Err(Object::Error(format!(
+            "wrong number of arguments: want={want}, got={}",
+            arguments.len()
+        )))
+    }
+}
+
+fn expect_array<'a>(name: &str, argument: &'a Object) -> Result<&'a [Object], Object> {
+    match argument {
+        Object::Array(elements) => Ok(elements),
+        _ => Err(Object::Error(format!(
+            "argument to `{name}` must be ARRAY, got {}",
+            argument.type_name()
+        ))),
+    }
+}
+
+fn len(arguments: &[Object]) -> Object {
+    if let Err(error) = check_argument_count(arguments, 1) {
+        return error;
+    }
+    let length = match &arguments[0] {
+        Object::String(value) => value.chars().count(),
+        Object::Array(elements) => elements.len(),
+        Object::Hash(pairs) => pairs.len(),
+        argument => {
+            return Object::Error(format!(
+                "argument to `len` not supported, got {}",
+                argument.type_name()
+            ));
+        }
+    };
+    i64::try_from(length).map_or_else(
+        |_| Object::Error(format!("integer overflow: len {length}")),
+        Object::Integer,
+    )
+}
+
+fn puts(arguments: &[Object]) -> Object {
+    for argument in arguments {
+        println!("{argument}");
+    }
+    Object::Null
+}
+
+fn first(arguments: &[Object]) -> Object {
+    if let Err(error) = check_argument_count(arguments, 1) {
+        return error;
+    }
+    match expect_array("first", &arguments[0]) {
+        Ok(elements) => elements.first().cloned().unwrap_or(Object::Null),
+        Err(error) => error,
+    }
+}
+
+fn last(arguments: &[Object]) -> Object {
+    if let Err(error) = check_argument_count(arguments, 1) {
+        return error;
+    }
+    match expect_array("last", &arguments[0]) {
+        Ok(elements) => elements.last().cloned().unwrap_or(Object::Null),
+        Err(error) => error,
+    }
+}
+
+/// Returns a new array holding everything but the first element, or `null`
+/// for an empty array.
+fn rest(arguments: &[Object]) -> Object {
+    if let Err(error) = check_argument_count(arguments, 1) {
+        return error;
+    }
+    match expect_array("rest", &arguments[0]) {
+        Ok([]) => Object::Null,
+        Ok([_, rest @ ..]) => Object::Array(rest.to_vec()),
+        Err(error) => error,
+    }
+}
+
+/// Returns a new array with the element appended; the original is unchanged.
+fn push(arguments: &[Object]) -> Object {
+    if let Err(error) = check_argument_count(arguments, 2) {
+        return error;
+    }
+    match expect_array("push", &arguments[0]) {
+        Ok(elements) => {
+            let mut elements = elements.to_vec();
+            elements.push(arguments[1].clone());
+            Object::Array(elements)
+        }
+        Err(error) => error,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        for builtin in BUILTINS {
+            assert_eq!(lookup(builtin.name), Some(*builtin));
+        }
+        assert_eq!(lookup("map"), None);
+    }
+
+    #[test]
+    fn test_rest_does_not_modify_argument() {
+        let array = Object::Array(vec![Object::Integer(1), Object::Integer(2)]);
+        let arguments = [array.clone()];
+        assert_eq!(rest(&arguments), Object::Array(vec![Object::Integer(2)]));
+        assert_eq!(arguments[0], array);
+    }
+}
@@ -0,0 +1,240 @@
+//! A canonical rendering of the AST that parenthesizes every operator
+//! application, including calls and indexing, so the grouping chosen by the
+//! parser is visible in the output. Unlike `Display`, which aims to read
+//! like source, this form exists to make precedence testable.
+
+use std::fmt::Write;
+
+use crate::ast::{BlockStatement, Expression, Program, Statement};
+
+pub fn program(program: &Program) -> String {
+    statements(&program.statements)
+}
+
+pub fn statement(statement: &Statement) -> String {
+    match statement {
+        Statement::LetStmt(let_stmt) => {
+            format!("let {} = {};", let_stmt.name, expression(&let_stmt.value))
+        }
+        Statement::AssignStatement(assign) => format!(
+            "{} {} {};",
+            assign.name,
+            assign.operator,
+            expression(&assign.value)
+        ),
+        Statement::ReturnStmt(return_stmt) => {
+            format!("return {};", expression(&return_stmt.return_value))
+        }
+        Statement::ExpressionStmt(expression_stmt) => expression(&expression_stmt.expression),
+    }
+}
+
+pub fn expression(expression: &Expression) -> String {
+    match expression {
+        Expression::Identifier(identifier) => identifier.to_string(),
+        Expression::IntegerLiteral(integer) => integer.to_string(),
+        Expression::StringLiteral(string) => string.to_string(),
+        Expression::Boolean(boolean) => boolean.to_string(),
+        Expression::PrefixExpression(prefix) => {
+            format!("({}{})", prefix.operator, self::expression(&prefix.right))
+        }
+        Expression::InfixExpression(infix) => format!(
+            "({} {} {})",
+            self::expression(&infix.left),
+            infix.operator,
+            self::expression(&infix.right)
+        ),
+        Expression::IfExpression(if_expression) => {
+            let mut output = format!(
+                "if {} {}",
+                self::expression(&if_expression.condition),
+                block(&if_expression.consequence)
+            );
+            if let Some(alternative) = &if_expression.alternative {
+                let _ = write!(output, " else {}", block(alternative));
+            }
+            output
+        }
+        Expression::FunctionLiteral(function) => {
+            let parameters: Vec<String> = function
+                .parameters
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            format!("fn({}) {}", parameters.join(", "), block(&function.body))
+        }
+        Expression::CallExpression(call) => format!(
+            "({}({}))",
+            self::expression(&call.function),
+            list(&call.arguments)
+        ),
+        Expression::ArrayLiteral(array) => format!("[{}]", list(&array.elements)),
+        Expression::IndexExpression(index) => format!(
+            "({}[{}])",
+            self::expression(&index.left),
+            self::expression(&index.index)
+        ),
+        Expression::HashLiteral(hash) => {
+            let pairs: Vec<String> = hash
+                .pairs
+                .iter()
+                .map(|(key, value)| {
+                    format!("{}: {}", self::expression(key), self::expression(value))
+                })
+                .collect();
+            format!("{{{}}}", pairs.join(", "))
+        }
+    }
+}
+
+fn block(block: &BlockStatement) -> String {
+    if block.statements.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", statements(&block.statements))
+    }
+}
+
+fn statements(statements: &[Statement]) -> String {
+    statements
+        .iter()
+        .map(statement)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn list(expressions: &[Expression]) -> String {
+    expressions
+        .iter()
+        .map(expression)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn canonical(input: &str) -> String {
+        let mut parser = Parser::new(input);
+        let parsed = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{input}: {:?}", parser.errors);
+        program(&parsed)
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let test_cases = [
+            // Prefix operators
+            ("-a", "(-a)"),
+            ("!a", "(!a)"),
+            ("!-a", "(!(-a))"),
+            ("--a", "(-(-a))"),
+            ("!!true", "(!(!true))"),
+            ("-a * b", "((-a) * b)"),
+            ("-a + b", "((-a) + b)"),
+            ("!a == b", "((!a) == b)"),
+            ("-(a + b)", "(-(a + b))"),
+            // Left associativity within a level
+            ("a + b + c", "((a + b) + c)"),
+            ("a + b - c", "((a + b) - c)"),
+            ("a - b - c", "((a - b) - c)"),
+            ("a * b * c", "((a * b) * c)"),
+            ("a * b / c", "((a * b) / c)"),
+            ("a / b * c", "((a / b) * c)"),
+            ("a == b == c", "((a == b) == c)"),
+            ("a != b == c", "((a != b) == c)"),
+            ("a < b < c", "((a < b) < c)"),
+            // Mixing levels
+            ("a + b * c", "(a + (b * c))"),
+            ("a * b + c", "((a * b) + c)"),
+            ("a + b / c", "(a + (b / c))"),
+            ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
+            ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
+            ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
+            ("5 >= 4 != 3 <= 4", "((5 >= 4) != (3 <= 4))"),
+            ("a + b > c * d", "((a + b) > (c * d))"),
+            (
+                "3 + 4 * 5 == 3 * 1 + 4 * 5",
+                "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
+            ),
+            ("true == false != true", "((true == false) != true)"),
+            ("3 > 5 == false", "((3 > 5) == false)"),
+            // Grouping
+            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+            ("(5 + 5) * 2", "((5 + 5) * 2)"),
+            ("2 / (5 + 5)", "(2 / (5 + 5))"),
+            ("-(5 + 5) * 2", "((-(5 + 5)) * 2)"),
+            ("!(true == true)", "(!(true == true))"),
+            ("((a))", "a"),
+            // Calls
+            ("add(a, b)", "(add(a, b))"),
+            ("add()", "(add())"),
+            ("a + add(b * c) + d", "((a + (add((b * c)))) + d)"),
+            (
+                "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+                "(add(a, b, 1, (2 * 3), (4 + 5), (add(6, (7 * 8)))))",
+            ),
+            (
+                "add(a + b + c * d / f + g)",
+                "(add((((a + b) + ((c * d) / f)) + g)))",
+            ),
+            ("-f(x)", "(-(f(x)))"),
+            ("!f(x)", "(!(f(x)))"),
+            ("f(x)(y)", "((f(x))(y))"),
+            ("(-f)(x)", "((-f)(x))"),
+            ("fn(x) { x }(5)", "(fn(x) { x }(5))"),
+            ("fn(x) { x }(5) + 1", "((fn(x) { x }(5)) + 1)"),
+            // Indexing
+            ("a[0]", "(a[0])"),
+            ("a[0][1]", "((a[0])[1])"),
+            ("-a[0]", "(-(a[0]))"),
+            ("-a[0] * b", "((-(a[0])) * b)"),
+            ("a[b + c]", "(a[(b + c)])"),
+            ("f(x)[0]", "((f(x))[0])"),
+            ("a[0](x)", "((a[0])(x))"),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "(add((a * (b[2])), (b[1]), (2 * ([1, 2][1]))))",
+            ),
+            // Operators inside literals and blocks
+            ("[1 + 2 * 3, -a]", "[(1 + (2 * 3)), (-a)]"),
+            ("{a + 1: b * 2}", "{(a + 1): (b * 2)}"),
+            ("if (a + b > c) { a * b }", "if ((a + b) > c) { (a * b) }"),
+            (
+                "if (!a) { -b } else { c - d - e }",
+                "if (!a) { (-b) } else { ((c - d) - e) }",
+            ),
+            ("fn(a, b) { a + b * 2 }", "fn(a, b) { (a + (b * 2)) }"),
+            ("fn() {}", "fn() {}"),
+            // Statements
+            ("let x = 1 + 2 * 3;", "let x = (1 + (2 * 3));"),
+            ("x -= -1 - 2;", "x -= ((-1) - 2);"),
+            ("return a < b == true;", "return ((a < b) == true);"),
+            ("a + b; -c", "(a + b) (-c)"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(canonical(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_literals() {
+        let test_cases = [
+            ("x", "x"),
+            ("42", "42"),
+            ("true", "true"),
+            (r#""a \"b\"""#, r#""a \"b\"""#),
+            ("[]", "[]"),
+            ("{}", "{}"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(canonical(input), expected, "input: {input}");
+        }
+    }
+}
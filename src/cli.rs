@@ -0,0 +1,468 @@
+use std::fmt;
+use std::io::Read;
+use std::path::PathBuf;
+
+use crate::compiler::CompileError;
+use crate::dump::{self, Tree};
+use crate::formatter;
+use crate::object::Object;
+use crate::parser::{ParseError, Parser};
+use crate::session::Session;
+
+pub const USAGE: &str = "\
+Usage: monkeyrs [--engine <eval|vm>] [--format <text|json>] [--check] [COMMAND]
+
+Commands:
+  (none)        start the interactive REPL
+  run <file>    run a script, reading it from stdin when <file> is `-`
+  -e <code>     evaluate <code> and print its value
+  tokens <file> print the tokens of a script with their positions
+  ast <file>    print the syntax tree of a script
+  fmt <file>    print a script in canonical style
+
+Options:
+  --engine <eval|vm>    run programs with the tree-walking evaluator
+                        (default) or the bytecode VM
+  --format <text|json>  output format for `tokens` and `ast` (default text)
+  --check               with `fmt`, print nothing and fail if the script is
+                        not already formatted
+  -h, --help            print this message
+
+Environment:
+  MONKEY_THEME  REPL colors as `class=style` entries separated by commas,
+                e.g. `keyword=bold red,integer=#ff8800,illegal=white on red`
+  NO_COLOR      when set, the REPL uses no colors
+
+Exit status:
+  0   success
+  1   the program failed with a runtime error, or `fmt --check` found
+      the script unformatted
+  2   the program was rejected before running (parse or compile errors)
+  64  the command line was invalid
+  66  the input could not be read";
+
+pub const EXIT_SUCCESS: u8 = 0;
+pub const EXIT_RUNTIME_ERROR: u8 = 1;
+pub const EXIT_UNFORMATTED: u8 = 1;
+pub const EXIT_REJECTED: u8 = 2;
+pub const EXIT_USAGE: u8 = 64;
+pub const EXIT_NO_INPUT: u8 = 66;
+
+/// The backend that runs parsed programs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    #[default]
+    Evaluator,
+    Vm,
+}
+
+/// How `tokens` and `ast` print their output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Path(PathBuf),
+    Stdin,
+}
+
+impl Input {
+    fn read(&self) -> std::io::Result<String> {
+        match self {
+            Input::Path(path) => std::fs::read_to_string(path),
+            Input::Stdin => {
+                let mut source = String::new();
+                std::io::stdin().read_to_string(&mut source)?;
+                Ok(source)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Path(path) => write!(f, "{}", path.display()),
+            Input::Stdin => write!(f, "<stdin>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Repl,
+    Run(Input),
+    Eval(String),
+    Tokens(Input, Format),
+    Ast(Input, Format),
+    Fmt { input: Input, check: bool },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub engine: Engine,
+    pub command: Command,
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: &[String]) -> Result<Cli, String> {
+    let mut engine = Engine::default();
+    let mut format = None;
+    // `--check` may come before or after `fmt`, so it is read up front.
+    let check = args.iter().any(|arg| arg == "--check");
+    let mut command = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "--engine" => {
+                engine = match args.next().map(String::as_str) {
+                    Some("eval") => Engine::Evaluator,
+                    Some("vm") => Engine::Vm,
+                    Some(other) => return Err(format!("unknown engine `{other}`")),
+                    None => return Err("`--engine` needs a value".to_string()),
+                };
+                continue;
+            }
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("text") => Some(Format::Text),
+                    Some("json") => Some(Format::Json),
+                    Some(other) => return Err(format!("unknown format `{other}`")),
+                    None => return Err("`--format` needs a value".to_string()),
+                };
+                continue;
+            }
+            "--check" => continue,
+            "-h" | "--help" => Command::Help,
+            "run" => Command::Run(input(arg, args.next())?),
+            "tokens" => Command::Tokens(input(arg, args.next())?, Format::Text),
+            "ast" => Command::Ast(input(arg, args.next())?, Format::Text),
+            "fmt" => {
+                let mut file = args.next();
+                if file.is_some_and(|file| file == "--check") {
+                    file = args.next();
+                }
+                Command::Fmt {
+                    input: input(arg, file)?,
+                    check,
+                }
+            }
+            "-e" => match args.next() {
+                Some(code) => Command::Eval(code.clone()),
+                None => return Err("`-e` needs code to evaluate".to_string()),
+            },
+            other => return Err(format!("unexpected argument `{other}`")),
+        };
+        if command.replace(next).is_some() {
+            return Err(format!("unexpected argument `{arg}`"));
+        }
+    }
+    let mut command = command.unwrap_or(Command::Repl);
+    if let Some(format) = format {
+        match &mut command {
+            Command::Tokens(_, output) | Command::Ast(_, output) => *output = format,
+            _ => return Err("`--format` only applies to `tokens` and `ast`".to_string()),
+        }
+    }
+    if check && !matches!(command, Command::Fmt { .. }) {
+        return Err("`--check` only applies to `fmt`".to_string());
+    }
+    Ok(Cli { engine, command })
+}
+
+fn input(command: &str, arg: Option<&String>) -> Result<Input, String> {
+    match arg.map(String::as_str) {
+        Some("-") => Ok(Input::Stdin),
+        Some(path) => Ok(Input::Path(PathBuf::from(path))),
+        None => Err(format!("`{command}` needs a file")),
+    }
+}
+
+/// Why a program did not produce a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    Parse(Vec<ParseError>),
+    Compile(CompileError),
+    Runtime(String),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Parse(_) | Failure::Compile(_) => EXIT_REJECTED,
+            Failure::Runtime(_) => EXIT_RUNTIME_ERROR,
+        }
+    }
+
+    /// Renders the failure for stderr, quoting `source` for parse errors.
+    pub fn render(&self, origin: &str, source: &str) -> String {
+        match self {
+            Failure::Parse(errors) => errors
+                .iter()
+                .map(|error| error.to_diagnostic().with_origin(origin).render(source))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Failure::Compile(error) => format!("error: {error}"),
+            Failure::Runtime(message) => format!("error: {message}"),
+        }
+    }
+}
+
+pub fn execute(source: &str, engine: Engine) -> Result<Object, Failure> {
+    Session::new(engine).execute(source)
+}
+
+/// Prints the tokens of `source`. Lex errors go to stderr after the
+/// stream, which still covers the whole input.
+fn dump_tokens(origin: &str, source: &str, format: Format) -> u8 {
+    let (tokens, errors) = dump::tokens(source);
+    match format {
+        Format::Text => print!("{}", dump::tokens_text(&tokens)),
+        Format::Json => println!("{}", dump::tokens_json(&tokens)),
+    }
+    let errors: Vec<_> = errors.into_iter().map(ParseError::Lex).collect();
+    report_parse_errors(origin, source, errors)
+}
+
+/// Prints the syntax tree of `source`, which may be partial when the
+/// parser recovered from errors.
+fn dump_ast(origin: &str, source: &str, format: Format) -> u8 {
+    let mut parser = Parser::new(source);
+    let tree = Tree::from_program(&parser.parse_program());
+    match format {
+        Format::Text => print!("{}", tree.to_text()),
+        Format::Json => println!("{}", tree.to_json()),
+    }
+    report_parse_errors(origin, source, parser.errors)
+}
+
+/// Prints `source` formatted, or with `check` only reports whether it
+/// already is.
+fn format(input: &Input, source: &str, check: bool) -> u8 {
+    let formatted = match formatter::format_source(source, formatter::DEFAULT_WIDTH) {
+        Ok(formatted) => formatted,
+        Err(errors) => return report_parse_errors(&input.to_string(), source, errors),
+    };
+    if !check {
+        print!("{formatted}");
+        EXIT_SUCCESS
+    } else if formatted == source {
+        EXIT_SUCCESS
+    } else {
+        eprintln!("error: {input} is not formatted");
+        EXIT_UNFORMATTED
+    }
+}
+
+fn report_parse_errors(origin: &str, source: &str, errors: Vec<ParseError>) -> u8 {
+    if errors.is_empty() {
+        return EXIT_SUCCESS;
+    }
+    let failure = Failure::Parse(errors);
+    eprintln!("{}", failure.render(origin, source));
+    failure.exit_code()
+}
+
+/// Runs a non-interactive command, returning the process exit status.
+pub fn run_command(engine: Engine, command: &Command) -> u8 {
+    let (origin, source, print_result) = match command {
+        Command::Run(input)
+        | Command::Tokens(input, _)
+        | Command::Ast(input, _)
+        | Command::Fmt { input, .. } => match input.read() {
+            Ok(source) => (input.to_string(), source, false),
+            Err(error) => {
+                eprintln!("error: cannot read {input}: {error}");
+                return EXIT_NO_INPUT;
+            }
+        },
+        Command::Eval(code) => ("<expr>".to_string(), code.clone(), true),
+        Command::Help => {
+            println!("{USAGE}");
+            return EXIT_SUCCESS;
+        }
+        Command::Repl => unreachable!("the REPL is not a batch command"),
+    };
+    match command {
+        Command::Tokens(_, format) => return dump_tokens(&origin, &source, *format),
+        Command::Ast(_, format) => return dump_ast(&origin, &source, *format),
+        Command::Fmt { input, check } => return format(input, &source, *check),
+        _ => {}
+    }
+    match execute(&source, engine) {
+        Ok(value) => {
+            if print_result && value != Object::Null {
+                println!("{value}");
+            }
+            EXIT_SUCCESS
+        }
+        Err(failure) => {
+            eprintln!("{}", failure.render(&origin, &source));
+            failure.exit_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let test_cases = [
+            (vec![], Engine::Evaluator, Command::Repl),
+            (vec!["--engine", "vm"], Engine::Vm, Command::Repl),
+            (
+                vec!["run", "script.monkey"],
+                Engine::Evaluator,
+                Command::Run(Input::Path(PathBuf::from("script.monkey"))),
+            ),
+            (
+                vec!["--engine", "vm", "run", "-"],
+                Engine::Vm,
+                Command::Run(Input::Stdin),
+            ),
+            (
+                vec!["-e", "1 + 2", "--engine", "eval"],
+                Engine::Evaluator,
+                Command::Eval("1 + 2".to_string()),
+            ),
+            (vec!["--help"], Engine::Evaluator, Command::Help),
+            (
+                vec!["tokens", "a.monkey"],
+                Engine::Evaluator,
+                Command::Tokens(Input::Path(PathBuf::from("a.monkey")), Format::Text),
+            ),
+            (
+                vec!["--format", "json", "ast", "-"],
+                Engine::Evaluator,
+                Command::Ast(Input::Stdin, Format::Json),
+            ),
+            (
+                vec!["ast", "a.monkey", "--format", "text"],
+                Engine::Evaluator,
+                Command::Ast(Input::Path(PathBuf::from("a.monkey")), Format::Text),
+            ),
+            (
+                vec!["fmt", "a.monkey"],
+                Engine::Evaluator,
+                Command::Fmt {
+                    input: Input::Path(PathBuf::from("a.monkey")),
+                    check: false,
+                },
+            ),
+            (
+                vec!["fmt", "--check", "-"],
+                Engine::Evaluator,
+                Command::Fmt {
+                    input: Input::Stdin,
+                    check: true,
+                },
+            ),
+            (
+                vec!["--check", "fmt", "a.monkey"],
+                Engine::Evaluator,
+                Command::Fmt {
+                    input: Input::Path(PathBuf::from("a.monkey")),
+                    check: true,
+                },
+            ),
+            (
+                vec!["fmt", "a.monkey", "--check"],
+                Engine::Evaluator,
+                Command::Fmt {
+                    input: Input::Path(PathBuf::from("a.monkey")),
+                    check: true,
+                },
+            ),
+        ];
+        for (input, engine, command) in test_cases {
+            assert_eq!(
+                parse_args(&args(&input)),
+                Ok(Cli { engine, command }),
+                "args: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let test_cases = [
+            (vec!["run"], "`run` needs a file"),
+            (vec!["-e"], "`-e` needs code to evaluate"),
+            (vec!["--engine"], "`--engine` needs a value"),
+            (vec!["--engine", "jit"], "unknown engine `jit`"),
+            (vec!["--verbose"], "unexpected argument `--verbose`"),
+            (vec!["run", "a", "b"], "unexpected argument `b`"),
+            (vec!["-e", "1", "run", "a"], "unexpected argument `run`"),
+            (vec!["tokens"], "`tokens` needs a file"),
+            (vec!["ast"], "`ast` needs a file"),
+            (vec!["--format"], "`--format` needs a value"),
+            (
+                vec!["--format", "yaml", "ast", "a"],
+                "unknown format `yaml`",
+            ),
+            (
+                vec!["--format", "json", "run", "a"],
+                "`--format` only applies to `tokens` and `ast`",
+            ),
+            (vec!["fmt"], "`fmt` needs a file"),
+            (
+                vec!["--check", "-e", "1"],
+                "`--check` only applies to `fmt`",
+            ),
+        ];
+        for (input, message) in test_cases {
+            assert_eq!(
+                parse_args(&args(&input)),
+                Err(message.to_string()),
+                "args: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_execute() {
+        for engine in [Engine::Evaluator, Engine::Vm] {
+            assert_eq!(execute("1 + 2", engine), Ok(Object::Integer(3)));
+            assert_eq!(execute("", engine), Ok(Object::Null));
+
+            let failure = execute("let x = ;", engine).unwrap_err();
+            assert!(matches!(failure, Failure::Parse(_)), "{failure:?}");
+            assert_eq!(failure.exit_code(), EXIT_REJECTED);
+
+            let failure = execute("1 / 0", engine).unwrap_err();
+            assert_eq!(failure, Failure::Runtime("division by zero".to_string()));
+            assert_eq!(failure.exit_code(), EXIT_RUNTIME_ERROR);
+        }
+        // Only the VM limits how many locals a function may have.
+        let parameters: Vec<String> = ('a'..='z')
+            .flat_map(|first| ('a'..='z').map(move |second| format!("p{first}{second}")))
+            .take(300)
+            .collect();
+        let source = format!("fn({}) {{ {} }}", parameters.join(", "), parameters[299]);
+        let failure = execute(&source, Engine::Vm).unwrap_err();
+        assert!(matches!(failure, Failure::Compile(_)), "{failure:?}");
+        assert_eq!(failure.exit_code(), EXIT_REJECTED);
+    }
+
+    #[test]
+    fn test_render_parse_failure() {
+        let source = "let x = @;";
+        let failure = execute(source, Engine::Evaluator).unwrap_err();
+        let rendered = failure.render("script.monkey", source);
+        assert!(
+            rendered.starts_with("error[E0005]: unexpected character `@`\n --> script.monkey:1:9"),
+            "{rendered}"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt::Write;
+
+/// A flat sequence of encoded instructions: an opcode byte followed by its
+/// big-endian operands.
+pub type Instructions = Vec<u8>;
+
+/// The most operands any opcode takes.
+pub const MAX_OPERANDS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Opcode {
+    Constant,
+    Pop,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    True,
+    False,
+    Null,
+    Equal,
+    NotEqual,
+    GreaterThan,
+    GreaterEqual,
+    LessThan,
+    LessEqual,
+    Minus,
+    Bang,
+    JumpNotTruthy,
+    Jump,
+    GetGlobal,
+    SetGlobal,
+    AssignGlobal,
+    GetLocal,
+    SetLocal,
+    GetBuiltin,
+    GetFree,
+    AssignFree,
+    CurrentClosure,
+    Array,
+    Hash,
+    Index,
+    Call,
+    ReturnValue,
+    Return,
+    Closure,
+}
+
+// Indexed by discriminant, so this must list the variants in declaration order.
+const OPCODES: [Opcode; 35] = [
+    Opcode::Constant,
+    Opcode::Pop,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Null,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::GreaterThan,
+    Opcode::GreaterEqual,
+    Opcode::LessThan,
+    Opcode::LessEqual,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::JumpNotTruthy,
+    Opcode::Jump,
+    Opcode::GetGlobal,
+    Opcode::SetGlobal,
+    Opcode::AssignGlobal,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::GetBuiltin,
+    Opcode::GetFree,
+    Opcode::AssignFree,
+    Opcode::CurrentClosure,
+    Opcode::Array,
+    Opcode::Hash,
+    Opcode::Index,
+    Opcode::Call,
+    Opcode::ReturnValue,
+    Opcode::Return,
+    Opcode::Closure,
+];
+
+impl Opcode {
+    /// The width in bytes of each operand this opcode takes.
+    pub fn operand_widths(self) -> &'static [usize] {
+        match self {
+            Opcode::Constant
+            | Opcode::JumpNotTruthy
+            | Opcode::Jump
+            | Opcode::GetGlobal
+            | Opcode::SetGlobal
+            | Opcode::AssignGlobal
+            | Opcode::Array
+            | Opcode::Hash => &[2],
+            Opcode::GetLocal
+            | Opcode::SetLocal
+            | Opcode::GetBuiltin
+            | Opcode::GetFree
+            | Opcode::AssignFree
+            | Opcode::Call => &[1],
+            Opcode::Closure => &[2, 1],
+            _ => &[],
+        }
+    }
+
+    /// The number of bytes the opcode and its operands occupy.
+    pub fn width(self) -> usize {
+        1 + self.operand_widths().iter().sum::<usize>()
+    }
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    fn try_from(byte: u8) -> Result<Opcode, u8> {
+        OPCODES.get(usize::from(byte)).copied().ok_or(byte)
+    }
+}
+
+/// Encodes a single instruction. Operands are truncated to their width, so
+/// callers are responsible for checking that they fit.
+pub fn make(opcode: Opcode, operands: &[usize]) -> Instructions {
+    let mut instruction = Vec::with_capacity(opcode.width());
+    instruction.push(opcode as u8);
+    for (operand, width) in operands.iter().zip(opcode.operand_widths()) {
+        let bytes = operand.to_be_bytes();
+        instruction.extend_from_slice(&bytes[bytes.len() - width..]);
+    }
+    instruction
+}
+
+/// Decodes the operands of `opcode` from the start of `instructions`,
+/// returning them along with the number of bytes read. Unused operand slots
+/// are zero.
+pub fn read_operands(opcode: Opcode, instructions: &[u8]) -> ([usize; MAX_OPERANDS], usize) {
+    let mut operands = [0; MAX_OPERANDS];
+    let mut offset = 0;
+    for (operand, width) in operands.iter_mut().zip(opcode.operand_widths()) {
+        *operand = instructions[offset..offset + width]
+            .iter()
+            .fold(0, |value, byte| (value << 8) | usize::from(*byte));
+        offset += width;
+    }
+    (operands, offset)
+}
+
+/// Renders instructions one per line, prefixed with their byte offset:
+///
+/// ```text
+/// 0000 OpConstant 0
+/// 0003 OpPop
+/// ```
+pub fn disassemble(instructions: &[u8]) -> String {
+    let mut output = String::new();
+    let mut position = 0;
+    while position < instructions.len() {
+        let Ok(opcode) = Opcode::try_from(instructions[position]) else {
+            let _ = writeln!(
+                output,
+                "{position:04} ERROR: unknown opcode {}",
+                instructions[position]
+            );
+            position += 1;
+            continue;
+        };
+        let (operands, read) = read_operands(opcode, &instructions[position + 1..]);
+        let _ = write!(output, "{position:04} Op{opcode:?}");
+        for operand in &operands[..opcode.operand_widths().len()] {
+            let _ = write!(output, " {operand}");
+        }
+        output.push('\n');
+        position += 1 + read;
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_opcode_table() {
+        for (byte, opcode) in OPCODES.iter().enumerate() {
+            assert_eq!(*opcode as usize, byte);
+            assert_eq!(Opcode::try_from(*opcode as u8), Ok(*opcode));
+        }
+        assert_eq!(Opcode::try_from(255), Err(255));
+    }
+
+    #[test]
+    fn test_make() {
+        let test_cases = [
+            (
+                Opcode::Constant,
+                vec![65534],
+                vec![Opcode::Constant as u8, 255, 254],
+            ),
+            (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+            (
+                Opcode::GetLocal,
+                vec![255],
+                vec![Opcode::GetLocal as u8, 255],
+            ),
+            (
+                Opcode::Closure,
+                vec![65534, 255],
+                vec![Opcode::Closure as u8, 255, 254, 255],
+            ),
+        ];
+        for (opcode, operands, expected) in test_cases {
+            assert_eq!(make(opcode, &operands), expected, "opcode: {opcode:?}");
+        }
+    }
+
+    #[test]
+    fn test_read_operands() {
+        let test_cases = [
+            (Opcode::Constant, vec![65535], 2),
+            (Opcode::GetLocal, vec![255], 1),
+            (Opcode::Closure, vec![65535, 255], 3),
+        ];
+        for (opcode, operands, bytes_read) in test_cases {
+            let instruction = make(opcode, &operands);
+            let (read, offset) = read_operands(opcode, &instruction[1..]);
+            assert_eq!(offset, bytes_read);
+            assert_eq!(&read[..operands.len()], operands.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let instructions = [
+            make(Opcode::Add, &[]),
+            make(Opcode::GetLocal, &[1]),
+            make(Opcode::Constant, &[2]),
+            make(Opcode::Constant, &[65535]),
+            make(Opcode::Closure, &[65535, 255]),
+        ]
+        .concat();
+        let expected = "\
+0000 OpAdd
+0001 OpGetLocal 1
+0003 OpConstant 2
+0006 OpConstant 65535
+0009 OpClosure 65535 255
+";
+        assert_eq!(disassemble(&instructions), expected);
+    }
+}
@@ -0,0 +1,827 @@
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast;
+use crate::builtins;
+use crate::code::{self, Instructions, Opcode};
+use crate::object::{Capture, CompiledFunction, Object};
+use crate::symbol_table::{Symbol, SymbolScope, SymbolTable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// An operand does not fit in its encoding, e.g. too many constants or
+    /// a jump past the end of a 64 KiB program.
+    OperandTooLarge {
+        opcode: Opcode,
+        operand: usize,
+    },
+    UnknownOperator(String),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::OperandTooLarge { opcode, operand } => {
+                write!(f, "operand {operand} is too large for Op{opcode:?}")
+            }
+            CompileError::UnknownOperator(operator) => write!(f, "unknown operator: {operator}"),
+        }
+    }
+}
+
+impl Error for CompileError {}
+
+/// The output of the compiler, ready to be run by the VM.
+#[derive(Debug)]
+pub struct Bytecode {
+    pub instructions: Instructions,
+    pub constants: Vec<Object>,
+    /// Names of the global slots, used to report reads of undefined globals.
+    pub global_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EmittedInstruction {
+    opcode: Opcode,
+    position: usize,
+}
+
+#[derive(Debug, Default)]
+struct CompilationScope {
+    instructions: Instructions,
+    last_instruction: Option<EmittedInstruction>,
+}
+
+const PLACEHOLDER: usize = 0xFFFF;
+
+pub struct Compiler {
+    constants: Vec<Object>,
+    symbol_table: SymbolTable,
+    scopes: Vec<CompilationScope>,
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler {
+            constants: vec![],
+            symbol_table: SymbolTable::new(),
+            scopes: vec![CompilationScope::default()],
+        }
+    }
+
+    /// Continues from the globals and constants of an earlier compilation,
+    /// so that a REPL input can refer to what previous inputs defined.
+    pub fn new_with_state(symbol_table: SymbolTable, constants: Vec<Object>) -> Compiler {
+        Compiler {
+            constants,
+            symbol_table,
+            ..Compiler::new()
+        }
+    }
+
+    /// The symbols defined so far, to pass to `new_with_state`.
+    pub fn symbol_table(&self) -> &SymbolTable {
+        &self.symbol_table
+    }
+
+    pub fn bytecode(self) -> Bytecode {
+        let global_names = self.symbol_table.global_names();
+        let mut scopes = self.scopes;
+        Bytecode {
+            instructions: scopes.pop().unwrap_or_default().instructions,
+            constants: self.constants,
+            global_names,
+        }
+    }
+
+    /// Compiles `program` so that, like `evaluator::eval_program`, it leaves
+    /// the value of its last statement as the result.
+    pub fn compile(&mut self, program: &ast::Program) -> Result<(), CompileError> {
+        for statement in &program.statements {
+            self.compile_statement(statement)?;
+        }
+        if !matches!(
+            program.statements.last(),
+            Some(ast::Statement::ExpressionStmt(_))
+        ) {
+            self.emit(Opcode::Null, &[])?;
+            self.emit(Opcode::Pop, &[])?;
+        }
+        Ok(())
+    }
+
+    fn compile_statement(&mut self, statement: &ast::Statement) -> Result<(), CompileError> {
+        match statement {
+            ast::Statement::LetStmt(let_stmt) => {
+                if let ast::Expression::FunctionLiteral(function) = &let_stmt.value {
+                    self.compile_function(function, Some(&let_stmt.name.value))?;
+                } else {
+                    self.compile_expression(&let_stmt.value)?;
+                }
+                let symbol = self.symbol_table.define(&let_stmt.name.value);
+                match symbol.scope {
+                    SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index])?,
+                    _ => self.emit(Opcode::SetLocal, &[symbol.index])?,
+                };
+            }
+            ast::Statement::AssignStatement(assign) => self.compile_assign_statement(assign)?,
+            ast::Statement::ReturnStmt(return_stmt) => {
+                self.compile_expression(&return_stmt.return_value)?;
+                self.emit(Opcode::ReturnValue, &[])?;
+            }
+            ast::Statement::ExpressionStmt(expression_stmt) => {
+                self.compile_expression(&expression_stmt.expression)?;
+                self.emit(Opcode::Pop, &[])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like the evaluator, a compound assignment reads its target as an
+    /// ordinary identifier before evaluating the value.
+    fn compile_assign_statement(
+        &mut self,
+        assign: &ast::AssignStatement,
+    ) -> Result<(), CompileError> {
+        self.symbol_table.forget_function_name(&assign.name.value);
+        match assign.infix_operator() {
+            Some(operator) => {
+                self.compile_identifier(&assign.name)?;
+                self.compile_expression(&assign.value)?;
+                self.emit(infix_opcode(operator)?, &[])?;
+            }
+            None => self.compile_expression(&assign.value)?,
+        }
+        let symbol = match self.symbol_table.resolve(&assign.name.value) {
+            Some(symbol) => symbol,
+            None => self.symbol_table.define_global(&assign.name.value),
+        };
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::AssignGlobal, &[symbol.index])?,
+            SymbolScope::Local => self.emit(Opcode::SetLocal, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::AssignFree, &[symbol.index])?,
+            SymbolScope::Function => unreachable!("function names are forgotten above"),
+        };
+        Ok(())
+    }
+
+    /// Compiles a block as an expression that leaves its value on the stack.
+    fn compile_block(&mut self, block: &ast::BlockStatement) -> Result<(), CompileError> {
+        for statement in &block.statements {
+            self.compile_statement(statement)?;
+        }
+        if self.last_instruction_is(Opcode::Pop) {
+            self.remove_last_instruction();
+        } else {
+            self.emit(Opcode::Null, &[])?;
+        }
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, expression: &ast::Expression) -> Result<(), CompileError> {
+        match expression {
+            ast::Expression::Identifier(identifier) => self.compile_identifier(identifier)?,
+            ast::Expression::IntegerLiteral(integer) => {
+                let index = self.add_constant(Object::Integer(integer.value));
+                self.emit(Opcode::Constant, &[index])?;
+            }
+            ast::Expression::StringLiteral(string) => {
+                let index = self.add_constant(Object::String(string.value.clone()));
+                self.emit(Opcode::Constant, &[index])?;
+            }
+            ast::Expression::Boolean(boolean) => {
+                let opcode = if boolean.value {
+                    Opcode::True
+                } else {
+                    Opcode::False
+                };
+                self.emit(opcode, &[])?;
+            }
+            ast::Expression::PrefixExpression(prefix) => {
+                self.compile_expression(&prefix.right)?;
+                let opcode = match prefix.operator.as_str() {
+                    "!" => Opcode::Bang,
+                    "-" => Opcode::Minus,
+                    operator => return Err(CompileError::UnknownOperator(operator.to_string())),
+                };
+                self.emit(opcode, &[])?;
+            }
+            ast::Expression::InfixExpression(infix) => {
+                self.compile_expression(&infix.left)?;
+                self.compile_expression(&infix.right)?;
+                let opcode = infix_opcode(&infix.operator)?;
+                self.emit(opcode, &[])?;
+            }
+            ast::Expression::IfExpression(if_expression) => {
+                self.compile_if_expression(if_expression)?;
+            }
+            ast::Expression::FunctionLiteral(function) => self.compile_function(function, None)?,
+            ast::Expression::CallExpression(call) => {
+                self.compile_expression(&call.function)?;
+                for argument in &call.arguments {
+                    self.compile_expression(argument)?;
+                }
+                self.emit(Opcode::Call, &[call.arguments.len()])?;
+            }
+            ast::Expression::ArrayLiteral(array) => {
+                for element in &array.elements {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Opcode::Array, &[array.elements.len()])?;
+            }
+            ast::Expression::IndexExpression(index) => {
+                self.compile_expression(&index.left)?;
+                self.compile_expression(&index.index)?;
+                self.emit(Opcode::Index, &[])?;
+            }
+            ast::Expression::HashLiteral(hash) => {
+                for (key, value) in &hash.pairs {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+                self.emit(Opcode::Hash, &[hash.pairs.len() * 2])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Builtins are resolved first, as in the evaluator. Names that are not
+    /// bound anywhere yet are assumed to be globals defined later; reading
+    /// one that is still unset is a runtime error.
+    fn compile_identifier(&mut self, identifier: &ast::Identifier) -> Result<(), CompileError> {
+        if let Some(index) = builtins::BUILTINS
+            .iter()
+            .position(|builtin| builtin.name == identifier.value)
+        {
+            self.emit(Opcode::GetBuiltin, &[index])?;
+            return Ok(());
+        }
+        let symbol = match self.symbol_table.resolve(&identifier.value) {
+            Some(symbol) => symbol,
+            None => self.symbol_table.define_global(&identifier.value),
+        };
+        self.load_symbol(&symbol)
+    }
+
+    fn load_symbol(&mut self, symbol: &Symbol) -> Result<(), CompileError> {
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::GetGlobal, &[symbol.index])?,
+            SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index])?,
+            SymbolScope::Function => self.emit(Opcode::CurrentClosure, &[])?,
+        };
+        Ok(())
+    }
+
+    fn compile_if_expression(
+        &mut self,
+        if_expression: &ast::IfExpression,
+    ) -> Result<(), CompileError> {
+        self.compile_expression(&if_expression.condition)?;
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[PLACEHOLDER])?;
+        self.compile_block(&if_expression.consequence)?;
+        let jump = self.emit(Opcode::Jump, &[PLACEHOLDER])?;
+
+        self.change_operand(jump_not_truthy, self.current_instructions().len())?;
+        match &if_expression.alternative {
+            Some(alternative) => self.compile_block(alternative)?,
+            None => {
+                self.emit(Opcode::Null, &[])?;
+            }
+        }
+        self.change_operand(jump, self.current_instructions().len())
+    }
+
+    /// Compiles a function literal into a closure. When the function is
+    /// bound by `let`, `name` lets its body refer to itself.
+    fn compile_function(
+        &mut self,
+        function: &ast::FunctionLiteral,
+        name: Option<&str>,
+    ) -> Result<(), CompileError> {
+        self.enter_scope();
+        if let Some(name) = name {
+            self.symbol_table.define_function_name(name);
+        }
+        for parameter in &function.parameters {
+            self.symbol_table.define(&parameter.value);
+        }
+        let mut declared = vec![];
+        declared_names(&function.body.statements, &mut declared);
+        for name in declared {
+            self.symbol_table.declare(name);
+        }
+        for statement in &function.body.statements {
+            self.compile_statement(statement)?;
+        }
+        if self.last_instruction_is(Opcode::Pop) {
+            self.replace_last_pop_with_return();
+        }
+        if !self.last_instruction_is(Opcode::ReturnValue) {
+            self.emit(Opcode::Return, &[])?;
+        }
+
+        let local_names = self.symbol_table.local_names();
+        let (instructions, free_symbols) = self.leave_scope();
+        let compiled = CompiledFunction {
+            instructions,
+            num_parameters: function.parameters.len(),
+            local_names,
+            free_names: free_symbols
+                .iter()
+                .map(|symbol| symbol.name.clone())
+                .collect(),
+            captures: free_symbols.iter().map(capture).collect(),
+            source: function.to_string(),
+        };
+        let index = self.add_constant(Object::CompiledFunction(Rc::new(compiled)));
+        self.emit(Opcode::Closure, &[index, free_symbols.len()])?;
+        Ok(())
+    }
+
+    fn add_constant(&mut self, object: Object) -> usize {
+        self.constants.push(object);
+        self.constants.len() - 1
+    }
+
+    /// Appends an instruction to the current scope, returning its position.
+    fn emit(&mut self, opcode: Opcode, operands: &[usize]) -> Result<usize, CompileError> {
+        check_operands(opcode, operands)?;
+        let scope = self.current_scope();
+        let position = scope.instructions.len();
+        scope
+            .instructions
+            .extend_from_slice(&code::make(opcode, operands));
+        scope.last_instruction = Some(EmittedInstruction { opcode, position });
+        Ok(position)
+    }
+
+    fn change_operand(&mut self, position: usize, operand: usize) -> Result<(), CompileError> {
+        let instructions = &mut self.current_scope().instructions;
+        let opcode = Opcode::try_from(instructions[position])
+            .expect("operands are only changed on emitted instructions");
+        check_operands(opcode, &[operand])?;
+        let instruction = code::make(opcode, &[operand]);
+        instructions[position..position + instruction.len()].copy_from_slice(&instruction);
+        Ok(())
+    }
+
+    fn last_instruction_is(&mut self, opcode: Opcode) -> bool {
+        self.current_scope()
+            .last_instruction
+            .is_some_and(|last| last.opcode == opcode)
+    }
+
+    /// Removes the last instruction. Only the most recent one is tracked, so
+    /// this must not be called twice in a row.
+    fn remove_last_instruction(&mut self) {
+        let scope = self.current_scope();
+        if let Some(last) = scope.last_instruction.take() {
+            scope.instructions.truncate(last.position);
+        }
+    }
+
+    fn replace_last_pop_with_return(&mut self) {
+        let scope = self.current_scope();
+        if let Some(last) = &mut scope.last_instruction {
+            scope.instructions[last.position] = Opcode::ReturnValue as u8;
+            last.opcode = Opcode::ReturnValue;
+        }
+    }
+
+    fn current_scope(&mut self) -> &mut CompilationScope {
+        self.scopes
+            .last_mut()
+            .expect("the main scope is never left")
+    }
+
+    fn current_instructions(&self) -> &Instructions {
+        &self.scopes[self.scopes.len() - 1].instructions
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(CompilationScope::default());
+        let outer = std::mem::take(&mut self.symbol_table);
+        self.symbol_table = SymbolTable::new_enclosed(outer);
+    }
+
+    /// Leaves a function scope, returning its instructions and the symbols
+    /// it captured from the enclosing scopes.
+    fn leave_scope(&mut self) -> (Instructions, Vec<Symbol>) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        let inner = std::mem::take(&mut self.symbol_table);
+        let free_symbols = inner.free_symbols.clone();
+        self.symbol_table = inner.into_outer().unwrap_or_default();
+        (scope.instructions, free_symbols)
+    }
+}
+
+/// How a closure finds `symbol`, a variable of the enclosing scope, when it
+/// is created.
+fn capture(symbol: &Symbol) -> Capture {
+    match symbol.scope {
+        SymbolScope::Local => Capture::Local(symbol.index),
+        SymbolScope::Free => Capture::Free(symbol.index),
+        SymbolScope::Function => Capture::Function,
+        SymbolScope::Global => unreachable!("globals are never captured"),
+    }
+}
+
+/// Collects the names bound by `let` in `statements`, including in nested
+/// blocks, which share their function's scope, but not in nested functions.
+fn declared_names<'a>(statements: &'a [ast::Statement], names: &mut Vec<&'a str>) {
+    for statement in statements {
+        match statement {
+            ast::Statement::LetStmt(let_stmt) => {
+                names.push(&let_stmt.name.value);
+                expression_declared_names(&let_stmt.value, names);
+            }
+            ast::Statement::AssignStatement(assign) => {
+                expression_declared_names(&assign.value, names);
+            }
+            ast::Statement::ReturnStmt(return_stmt) => {
+                expression_declared_names(&return_stmt.return_value, names);
+            }
+            ast::Statement::ExpressionStmt(expression_stmt) => {
+                expression_declared_names(&expression_stmt.expression, names);
+            }
+        }
+    }
+}
+
+fn expression_declared_names<'a>(expression: &'a ast::Expression, names: &mut Vec<&'a str>) {
+    match expression {
+        ast::Expression::Identifier(_)
+        | ast::Expression::IntegerLiteral(_)
+        | ast::Expression::StringLiteral(_)
+        | ast::Expression::Boolean(_)
+        | ast::Expression::FunctionLiteral(_) => {}
+        ast::Expression::PrefixExpression(prefix) => {
+            expression_declared_names(&prefix.right, names);
+        }
+        ast::Expression::InfixExpression(infix) => {
+            expression_declared_names(&infix.left, names);
+            expression_declared_names(&infix.right, names);
+        }
+        ast::Expression::IfExpression(if_expression) => {
+            expression_declared_names(&if_expression.condition, names);
+            declared_names(&if_expression.consequence.statements, names);
+            if let Some(alternative) = &if_expression.alternative {
+                declared_names(&alternative.statements, names);
+            }
+        }
+        ast::Expression::CallExpression(call) => {
+            expression_declared_names(&call.function, names);
+            for argument in &call.arguments {
+                expression_declared_names(argument, names);
+            }
+        }
+        ast::Expression::ArrayLiteral(array) => {
+            for element in &array.elements {
+                expression_declared_names(element, names);
+            }
+        }
+        ast::Expression::IndexExpression(index) => {
+            expression_declared_names(&index.left, names);
+            expression_declared_names(&index.index, names);
+        }
+        ast::Expression::HashLiteral(hash) => {
+            for (key, value) in &hash.pairs {
+                expression_declared_names(key, names);
+                expression_declared_names(value, names);
+            }
+        }
+    }
+}
+
+fn infix_opcode(operator: &str) -> Result<Opcode, CompileError> {
+    match operator {
+        "+" => Ok(Opcode::Add),
+        "-" => Ok(Opcode::Sub),
+        "*" => Ok(Opcode::Mul),
+        "/" => Ok(Opcode::Div),
+        "==" => Ok(Opcode::Equal),
+        "!=" => Ok(Opcode::NotEqual),
+        ">" => Ok(Opcode::GreaterThan),
+        ">=" => Ok(Opcode::GreaterEqual),
+        "<" => Ok(Opcode::LessThan),
+        "<=" => Ok(Opcode::LessEqual),
+        _ => Err(CompileError::UnknownOperator(operator.to_string())),
+    }
+}
+
+fn check_operands(opcode: Opcode, operands: &[usize]) -> Result<(), CompileError> {
+    for (&operand, width) in operands.iter().zip(opcode.operand_widths()) {
+        if operand >> (width * 8) != 0 {
+            return Err(CompileError::OperandTooLarge { opcode, operand });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::code::make;
+    use crate::parser::Parser;
+
+    fn compile(input: &str) -> Bytecode {
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        compiler.bytecode()
+    }
+
+    fn assert_instructions(actual: &[u8], expected: &[Instructions]) {
+        let expected = expected.concat();
+        assert_eq!(
+            code::disassemble(actual),
+            code::disassemble(&expected),
+            "instructions differ"
+        );
+    }
+
+    fn function(constant: &Object) -> &CompiledFunction {
+        let Object::CompiledFunction(function) = constant else {
+            panic!("Expected CompiledFunction, got {constant:?}");
+        };
+        function
+    }
+
+    fn function_instructions(constant: &Object) -> &[u8] {
+        &function(constant).instructions
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let bytecode = compile("1 + 2; -3");
+        assert_eq!(
+            bytecode.constants,
+            [Object::Integer(1), Object::Integer(2), Object::Integer(3)]
+        );
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_comparisons_keep_operand_order() {
+        let bytecode = compile("1 < 2");
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::LessThan, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let bytecode = compile("if (true) { 10 }; 3333;");
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::True, &[]),
+                make(Opcode::JumpNotTruthy, &[10]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Jump, &[11]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_global_let_statements() {
+        let bytecode = compile("let one = 1; let two = one; two;");
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::SetGlobal, &[1]),
+                make(Opcode::GetGlobal, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+        assert_eq!(bytecode.global_names, ["one", "two"]);
+    }
+
+    #[test]
+    fn test_program_ending_in_let_yields_null() {
+        let bytecode = compile("let x = 1;");
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Null, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        let bytecode = compile(r#"[1, 2][0]; {"a": 1}"#);
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Array, &[2]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Index, &[]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[3]),
+                make(Opcode::Constant, &[4]),
+                make(Opcode::Hash, &[2]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let bytecode = compile("fn() { 5 + 10 }; fn() { }; fn() { let x = 1; }");
+        assert_instructions(
+            function_instructions(&bytecode.constants[2]),
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        );
+        assert_instructions(
+            function_instructions(&bytecode.constants[3]),
+            &[make(Opcode::Return, &[])],
+        );
+        assert_instructions(
+            function_instructions(&bytecode.constants[5]),
+            &[
+                make(Opcode::Constant, &[4]),
+                make(Opcode::SetLocal, &[0]),
+                make(Opcode::Return, &[]),
+            ],
+        );
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Closure, &[2, 0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Closure, &[3, 0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Closure, &[5, 0]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_closures_and_builtins() {
+        let bytecode = compile("fn(a) { fn(b) { len(a) + b } }");
+        assert_instructions(
+            function_instructions(&bytecode.constants[0]),
+            &[
+                make(Opcode::GetBuiltin, &[0]),
+                make(Opcode::GetFree, &[0]),
+                make(Opcode::Call, &[1]),
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Add, &[]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        );
+        assert_instructions(
+            function_instructions(&bytecode.constants[1]),
+            &[
+                make(Opcode::Closure, &[0, 1]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        );
+        assert_eq!(
+            function(&bytecode.constants[0]).captures,
+            [Capture::Local(0)]
+        );
+    }
+
+    #[test]
+    fn test_recursive_functions() {
+        let bytecode = compile("let f = fn(x) { f(x - 1) };");
+        assert_instructions(
+            function_instructions(&bytecode.constants[1]),
+            &[
+                make(Opcode::CurrentClosure, &[]),
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Sub, &[]),
+                make(Opcode::Call, &[1]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_unbound_names_become_globals() {
+        let bytecode = compile("let f = fn() { g }; let g = 1;");
+        assert_instructions(
+            function_instructions(&bytecode.constants[0]),
+            &[
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        );
+        assert_eq!(bytecode.global_names, ["g", "f"]);
+    }
+
+    #[test]
+    fn test_assign_statements() {
+        let bytecode = compile("let x = 1; x += 2; fn(y) { y = 3 }");
+        assert_instructions(
+            &bytecode.instructions,
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::AssignGlobal, &[0]),
+                make(Opcode::Closure, &[3, 0]),
+                make(Opcode::Pop, &[]),
+            ],
+        );
+        assert_instructions(
+            function_instructions(&bytecode.constants[3]),
+            &[
+                make(Opcode::Constant, &[2]),
+                make(Opcode::SetLocal, &[0]),
+                make(Opcode::Return, &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_assign_to_captured_variable() {
+        let bytecode = compile("fn(x) { fn() { x = 1 } }");
+        assert_instructions(
+            function_instructions(&bytecode.constants[1]),
+            &[
+                make(Opcode::Constant, &[0]),
+                make(Opcode::AssignFree, &[0]),
+                make(Opcode::Return, &[]),
+            ],
+        );
+        assert_eq!(
+            function(&bytecode.constants[1]).captures,
+            [Capture::Local(0)]
+        );
+    }
+
+    #[test]
+    fn test_capture_names_declared_later() {
+        let bytecode = compile("fn() { let f = fn() { b }; let b = 2; f() }");
+        // `b` gets its slot when `f` captures it, before `f` itself is bound.
+        let inner = function(&bytecode.constants[0]);
+        assert_eq!(inner.captures, [Capture::Local(0)]);
+        assert_eq!(inner.free_names, ["b"]);
+        assert_eq!(function(&bytecode.constants[2]).local_names, ["b", "f"]);
+        assert_eq!(bytecode.global_names, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_too_many_locals() {
+        // Identifiers are alphabetic, so name the parameters `paa`, `pab`, ...
+        let parameters: Vec<String> = ('a'..='z')
+            .flat_map(|first| ('a'..='z').map(move |second| format!("p{first}{second}")))
+            .take(300)
+            .collect();
+        let input = format!("fn({}) {{ {} }}", parameters.join(", "), parameters[299]);
+        let mut parser = Parser::new(&input);
+        let program = parser.parse_program();
+        let error = Compiler::new().compile(&program).unwrap_err();
+        assert_eq!(
+            error,
+            CompileError::OperandTooLarge {
+                opcode: Opcode::GetLocal,
+                operand: 299
+            }
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use reedline::{Completer, Span, Suggestion};
+
+use crate::builtins::BUILTINS;
+use crate::lexer::Lexer;
+use crate::token::{KEYWORDS, TokenType};
+
+/// The names bound by `let` during a REPL session. The editor requires its
+/// completer to be `Send`, so the names are shared behind a mutex rather
+/// than read from the `Rc`-based environment.
+pub type SessionNames = Arc<Mutex<BTreeSet<String>>>;
+
+/// Completes the word under the cursor from the keywords, the builtins and
+/// the session's bindings.
+pub struct MonkeyCompleter {
+    names: SessionNames,
+}
+
+impl MonkeyCompleter {
+    pub fn new(names: SessionNames) -> MonkeyCompleter {
+        MonkeyCompleter { names }
+    }
+
+    /// Every candidate with its description, keywords first, without
+    /// duplicates.
+    fn candidates(&self) -> Vec<(String, &'static str)> {
+        let mut candidates: Vec<(String, &'static str)> = vec![];
+        let keywords = KEYWORDS.iter().map(|(keyword, _)| (*keyword, "keyword"));
+        let builtins = BUILTINS.iter().map(|builtin| (builtin.name, "builtin"));
+        for (name, description) in keywords.chain(builtins) {
+            candidates.push((name.to_string(), description));
+        }
+        let names = self.names.lock().unwrap_or_else(PoisonError::into_inner);
+        for name in names.iter() {
+            if !candidates.iter().any(|(candidate, _)| candidate == name) {
+                candidates.push((name.clone(), "binding"));
+            }
+        }
+        candidates
+    }
+}
+
+/// The start of the word ending at `pos`, or `None` when the cursor is
+/// inside a string or comment, where nothing should be completed.
+fn word_start(line: &str, pos: usize) -> Option<usize> {
+    let mut lexer = Lexer::new(&line[..pos]).with_trivia();
+    let mut last = None;
+    loop {
+        let token = lexer.next_token();
+        if let Some(comment) = token.leading_trivia.last() {
+            last = Some(comment.clone());
+        }
+        if token.is_type(TokenType::Eof) {
+            break;
+        }
+        last = Some(token);
+    }
+    match last {
+        Some(token) if token.span.end == pos => match token.token_type {
+            TokenType::String | TokenType::Comment => None,
+            token_type
+                if token_type == TokenType::Identifier
+                    || KEYWORDS.iter().any(|(_, keyword)| *keyword == token_type) =>
+            {
+                Some(token.span.start)
+            }
+            _ => Some(pos),
+        },
+        _ => Some(pos),
+    }
+}
+
+impl Completer for MonkeyCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let Some(start) = word_start(line, pos) else {
+            return vec![];
+        };
+        let prefix = &line[start..pos];
+        self.candidates()
+            .into_iter()
+            .filter(|(candidate, _)| candidate.starts_with(prefix))
+            .map(|(value, description)| Suggestion {
+                value,
+                description: Some(description.to_string()),
+                style: None,
+                extra: None,
+                span: Span::new(start, pos),
+                append_whitespace: false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn complete(names: &[&str], line: &str, pos: usize) -> Vec<(String, usize, usize)> {
+        let names = names.iter().map(ToString::to_string).collect();
+        let mut completer = MonkeyCompleter::new(Arc::new(Mutex::new(names)));
+        completer
+            .complete(line, pos)
+            .into_iter()
+            .map(|suggestion| (suggestion.value, suggestion.span.start, suggestion.span.end))
+            .collect()
+    }
+
+    fn values(names: &[&str], line: &str) -> Vec<String> {
+        complete(names, line, line.len())
+            .into_iter()
+            .map(|(value, _, _)| value)
+            .collect()
+    }
+
+    #[test]
+    fn test_complete() {
+        let names = ["length", "lemon", "fib"];
+        let test_cases = [
+            ("le", vec!["let", "len", "lemon", "length"]),
+            ("let x = fi", vec!["first", "fib"]),
+            ("re", vec!["return", "rest"]),
+            ("puts(f", vec!["fn", "false", "first", "fib"]),
+            ("lemon", vec!["lemon"]),
+            ("x", vec![]),
+        ];
+        for (line, expected) in test_cases {
+            assert_eq!(values(&names, line), expected, "line: {line}");
+        }
+    }
+
+    #[test]
+    fn test_complete_span() {
+        assert_eq!(
+            complete(&[], "1 + le", 6),
+            [("let".to_string(), 4, 6), ("len".to_string(), 4, 6)]
+        );
+        // Only the part of the word before the cursor is the prefix.
+        assert_eq!(
+            complete(&[], "pushed", 2),
+            [("puts".to_string(), 0, 2), ("push".to_string(), 0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_complete_without_prefix() {
+        let all = values(&["x"], "let y = ");
+        assert_eq!(all.len(), KEYWORDS.len() + BUILTINS.len() + 1);
+        assert_eq!(values(&[], "f(1,").len(), KEYWORDS.len() + BUILTINS.len());
+    }
+
+    #[test]
+    fn test_no_completion_in_strings_or_comments() {
+        assert_eq!(values(&[], "\"le"), Vec::<String>::new());
+        assert_eq!(values(&[], "puts(\"le\""), Vec::<String>::new());
+        assert_eq!(values(&[], "1 // le"), Vec::<String>::new());
+        assert_eq!(values(&[], "/* le"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_binding_shadowing_builtin_is_listed_once() {
+        assert_eq!(values(&["len"], "len"), ["len"]);
+    }
+}
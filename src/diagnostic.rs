@@ -0,0 +1,164 @@
+use std::fmt::Write;
+
+use crate::token::Span;
+
+/// A renderable error report pointing at a region of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    pub label: String,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+    /// Where the source came from, such as a file path.
+    pub origin: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: String, span: Span, label: String) -> Diagnostic {
+        Diagnostic {
+            code,
+            message,
+            span,
+            label,
+            notes: vec![],
+            help: None,
+            origin: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+
+    #[must_use]
+    pub fn with_help(mut self, help: String) -> Diagnostic {
+        self.help = Some(help);
+        self
+    }
+
+    #[must_use]
+    pub fn with_origin(mut self, origin: &str) -> Diagnostic {
+        self.origin = Some(origin.to_string());
+        self
+    }
+
+    /// Renders the diagnostic against `source`, quoting the offending line and
+    /// underlining the span with carets:
+    ///
+    /// ```text
+    /// error[E0001]: expected `)`, found `;`
+    ///  --> 1:15
+    ///   |
+    /// 1 | let x = (1 + 2;
+    ///   |               ^ expected `)`
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_number = self.span.line.max(1);
+        let line = source.lines().nth(line_number - 1).unwrap_or_default();
+        let gutter = " ".repeat(line_number.to_string().len());
+        let offset = self.span.column.saturating_sub(1);
+        let width = source
+            .get(self.span.start..self.span.end)
+            .map_or(0, |text| {
+                text.lines().next().unwrap_or_default().chars().count()
+            })
+            .max(1);
+
+        let mut rendered = format!("error[{}]: {}\n", self.code, self.message);
+        match &self.origin {
+            Some(origin) => {
+                let _ = writeln!(rendered, "{gutter}--> {origin}:{}", self.span);
+            }
+            None => {
+                let _ = writeln!(rendered, "{gutter}--> {}", self.span);
+            }
+        }
+        let _ = writeln!(rendered, "{gutter} |");
+        let _ = writeln!(rendered, "{line_number} | {line}");
+        let _ = write!(
+            rendered,
+            "{gutter} | {}{} {}",
+            " ".repeat(offset),
+            "^".repeat(width),
+            self.label
+        );
+        if !self.notes.is_empty() || self.help.is_some() {
+            let _ = write!(rendered, "\n{gutter} |");
+        }
+        for note in &self.notes {
+            let _ = write!(rendered, "\n{gutter} = note: {note}");
+        }
+        if let Some(help) = &self.help {
+            let _ = write!(rendered, "\n{gutter} = help: {help}");
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let source = "let a = 1;\nlet x = (1 + 2;";
+        let diagnostic = Diagnostic::new(
+            "E0001",
+            "expected `)`, found `;`".to_string(),
+            Span::new(25, 26, 2, 15),
+            "expected `)`".to_string(),
+        )
+        .with_note("the `(` opened here is never closed".to_string())
+        .with_help("insert `)`".to_string());
+        let expected = "\
+error[E0001]: expected `)`, found `;`
+ --> 2:15
+  |
+2 | let x = (1 + 2;
+  |               ^ expected `)`
+  |
+  = note: the `(` opened here is never closed
+  = help: insert `)`";
+        assert_eq!(diagnostic.render(source), expected);
+    }
+
+    #[test]
+    fn test_render_with_origin() {
+        let diagnostic = Diagnostic::new(
+            "E0005",
+            "unexpected character `@`".to_string(),
+            Span::new(0, 1, 1, 1),
+            "not valid in Monkey source".to_string(),
+        )
+        .with_origin("script.monkey");
+        let expected = "\
+error[E0005]: unexpected character `@`
+ --> script.monkey:1:1
+  |
+1 | @
+  | ^ not valid in Monkey source";
+        assert_eq!(diagnostic.render("@"), expected);
+    }
+
+    #[test]
+    fn test_render_underlines_whole_span() {
+        let source = "let x = 99999999999999999999;";
+        let diagnostic = Diagnostic::new(
+            "E0003",
+            "integer literal is too large".to_string(),
+            Span::new(8, 28, 1, 9),
+            "does not fit in 64 bits".to_string(),
+        );
+        let expected = "\
+error[E0003]: integer literal is too large
+ --> 1:9
+  |
+1 | let x = 99999999999999999999;
+  |         ^^^^^^^^^^^^^^^^^^^^ does not fit in 64 bits";
+        assert_eq!(diagnostic.render(source), expected);
+    }
+}
@@ -0,0 +1,485 @@
+use std::fmt::{self, Write};
+
+use crate::ast::{self, Node};
+use crate::code;
+use crate::compiler::Bytecode;
+use crate::json::Json;
+use crate::lexer::{LexError, Lexer};
+use crate::object::Object;
+use crate::token::{Span, Token, TokenType};
+
+/// Lexes `source` including comments, returning every token up to and
+/// including `Eof`, along with any errors.
+pub fn tokens(source: &str) -> (Vec<Token>, Vec<LexError>) {
+    let mut lexer = Lexer::new(source).with_trivia();
+    let mut tokens = vec![];
+    loop {
+        let mut token = lexer.next_token();
+        let is_eof = token.is_type(TokenType::Eof);
+        tokens.append(&mut token.leading_trivia);
+        tokens.push(token);
+        if is_eof {
+            break;
+        }
+    }
+    (tokens, lexer.take_errors())
+}
+
+/// Prints one token per line with its position, type and literal.
+pub fn tokens_text(tokens: &[Token]) -> String {
+    let mut output = String::new();
+    for token in tokens {
+        let position = format!("{}:{}", token.span.line, token.span.column);
+        let range = format!("{}..{}", token.span.start, token.span.end);
+        let token_type = format!("{:?}", token.token_type);
+        let mut row = format!("{position:<8} {range:<10} {token_type:<14}");
+        if !token.is_type(TokenType::Eof) {
+            let _ = write!(row, " {:?}", token.literal);
+        }
+        output.push_str(row.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+pub fn tokens_json(tokens: &[Token]) -> Json {
+    Json::Array(
+        tokens
+            .iter()
+            .map(|token| {
+                Json::Object(vec![
+                    ("type", Json::String(format!("{:?}", token.token_type))),
+                    ("literal", Json::string(&token.literal)),
+                    ("span", span_json(token.span)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn span_json(span: Span) -> Json {
+    let number = |value: usize| Json::Number(i64::try_from(value).unwrap_or(i64::MAX));
+    Json::Object(vec![
+        ("start", number(span.start)),
+        ("end", number(span.end)),
+        ("line", number(span.line)),
+        ("column", number(span.column)),
+    ])
+}
+
+/// Disassembles the main instructions of `bytecode`, then lists its
+/// constants from index `first` on, each compiled function followed by its
+/// own instructions indented.
+pub fn bytecode_text(bytecode: &Bytecode, first: usize) -> String {
+    let mut output = code::disassemble(&bytecode.instructions);
+    for (index, constant) in bytecode.constants.iter().enumerate().skip(first) {
+        let _ = write!(output, "\nconstant {index}: {constant}\n");
+        if let Object::CompiledFunction(function) = constant {
+            for line in code::disassemble(&function.instructions).lines() {
+                let _ = writeln!(output, "  {line}");
+            }
+        }
+    }
+    output
+}
+
+/// The payload of an AST node, such as an identifier's name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Name(String),
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Name(name) => write!(f, "{name}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Boolean(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+/// A uniform view of an AST node, so the tree can be rendered as text or
+/// JSON without either renderer knowing every node type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    /// The field of the parent this node fills, if any.
+    pub role: Option<&'static str>,
+    pub kind: &'static str,
+    pub value: Option<Value>,
+    pub span: Span,
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    fn new(kind: &'static str, span: Span) -> Tree {
+        Tree {
+            role: None,
+            kind,
+            value: None,
+            span,
+            children: vec![],
+        }
+    }
+
+    fn value(mut self, value: Value) -> Tree {
+        self.value = Some(value);
+        self
+    }
+
+    fn role(mut self, role: &'static str) -> Tree {
+        self.role = Some(role);
+        self
+    }
+
+    fn child(mut self, child: Tree) -> Tree {
+        self.children.push(child);
+        self
+    }
+
+    fn children(mut self, children: impl IntoIterator<Item = Tree>) -> Tree {
+        self.children.extend(children);
+        self
+    }
+
+    pub fn from_program(program: &ast::Program) -> Tree {
+        Tree::new("Program", program.span()).children(program.statements.iter().map(statement))
+    }
+
+    /// Renders the tree with two spaces of indentation per level:
+    ///
+    /// ```text
+    /// Program (1:1)
+    ///   LetStmt (1:1)
+    ///     name: Identifier x (1:5)
+    ///     value: IntegerLiteral 5 (1:9)
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut output = String::new();
+        self.write_text(0, &mut output);
+        output
+    }
+
+    fn write_text(&self, depth: usize, output: &mut String) {
+        output.push_str(&"  ".repeat(depth));
+        if let Some(role) = self.role {
+            let _ = write!(output, "{role}: ");
+        }
+        output.push_str(self.kind);
+        if let Some(value) = &self.value {
+            let _ = write!(output, " {value}");
+        }
+        let _ = writeln!(output, " ({})", self.span);
+        for child in &self.children {
+            child.write_text(depth + 1, output);
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut fields = vec![("kind", Json::string(self.kind))];
+        if let Some(role) = self.role {
+            fields.push(("role", Json::string(role)));
+        }
+        if let Some(value) = &self.value {
+            let value = match value {
+                Value::Name(value) | Value::String(value) => Json::string(value),
+                Value::Integer(value) => Json::Number(*value),
+                Value::Boolean(value) => Json::Bool(*value),
+            };
+            fields.push(("value", value));
+        }
+        fields.push(("span", span_json(self.span)));
+        fields.push((
+            "children",
+            Json::Array(self.children.iter().map(Tree::to_json).collect()),
+        ));
+        Json::Object(fields)
+    }
+}
+
+fn statement(statement: &ast::Statement) -> Tree {
+    match statement {
+        ast::Statement::LetStmt(let_stmt) => Tree::new("LetStmt", let_stmt.span())
+            .child(identifier(&let_stmt.name).role("name"))
+            .child(expression(&let_stmt.value).role("value")),
+        ast::Statement::AssignStatement(assign) => Tree::new("AssignStatement", assign.span())
+            .value(Value::Name(assign.operator.clone()))
+            .child(identifier(&assign.name).role("name"))
+            .child(expression(&assign.value).role("value")),
+        ast::Statement::ReturnStmt(return_stmt) => Tree::new("ReturnStmt", return_stmt.span())
+            .child(expression(&return_stmt.return_value).role("value")),
+        ast::Statement::ExpressionStmt(expression_stmt) => {
+            Tree::new("ExpressionStmt", expression_stmt.span())
+                .child(expression(&expression_stmt.expression).role("expression"))
+        }
+    }
+}
+
+fn block(block: &ast::BlockStatement) -> Tree {
+    Tree::new("BlockStatement", block.span()).children(block.statements.iter().map(statement))
+}
+
+fn identifier(identifier: &ast::Identifier) -> Tree {
+    Tree::new("Identifier", identifier.span()).value(Value::Name(identifier.value.clone()))
+}
+
+fn expression(expression: &ast::Expression) -> Tree {
+    let span = expression.span();
+    match expression {
+        ast::Expression::Identifier(name) => identifier(name),
+        ast::Expression::IntegerLiteral(integer) => {
+            Tree::new("IntegerLiteral", span).value(Value::Integer(integer.value))
+        }
+        ast::Expression::StringLiteral(string) => {
+            Tree::new("StringLiteral", span).value(Value::String(string.value.clone()))
+        }
+        ast::Expression::Boolean(boolean) => {
+            Tree::new("Boolean", span).value(Value::Boolean(boolean.value))
+        }
+        ast::Expression::PrefixExpression(prefix) => Tree::new("PrefixExpression", span)
+            .value(Value::Name(prefix.operator.clone()))
+            .child(self::expression(&prefix.right).role("right")),
+        ast::Expression::InfixExpression(infix) => Tree::new("InfixExpression", span)
+            .value(Value::Name(infix.operator.clone()))
+            .child(self::expression(&infix.left).role("left"))
+            .child(self::expression(&infix.right).role("right")),
+        ast::Expression::IfExpression(if_expression) => {
+            let tree = Tree::new("IfExpression", span)
+                .child(self::expression(&if_expression.condition).role("condition"))
+                .child(block(&if_expression.consequence).role("consequence"));
+            match &if_expression.alternative {
+                Some(alternative) => tree.child(block(alternative).role("alternative")),
+                None => tree,
+            }
+        }
+        ast::Expression::FunctionLiteral(function) => Tree::new("FunctionLiteral", span)
+            .children(
+                function
+                    .parameters
+                    .iter()
+                    .map(|parameter| identifier(parameter).role("parameter")),
+            )
+            .child(block(&function.body).role("body")),
+        ast::Expression::CallExpression(call) => Tree::new("CallExpression", span)
+            .child(self::expression(&call.function).role("function"))
+            .children(
+                call.arguments
+                    .iter()
+                    .map(|argument| self::expression(argument).role("argument")),
+            ),
+        ast::Expression::ArrayLiteral(array) => Tree::new("ArrayLiteral", span).children(
+            array
+                .elements
+                .iter()
+                .map(|element| self::expression(element).role("element")),
+        ),
+        ast::Expression::IndexExpression(index) => Tree::new("IndexExpression", span)
+            .child(self::expression(&index.left).role("left"))
+            .child(self::expression(&index.index).role("index")),
+        ast::Expression::HashLiteral(hash) => {
+            Tree::new("HashLiteral", span).children(hash.pairs.iter().map(|(key, value)| {
+                Tree::new("HashPair", key.span().to(value.span()))
+                    .child(self::expression(key).role("key"))
+                    .child(self::expression(value).role("value"))
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compiler::Compiler;
+    use crate::parser::Parser;
+
+    fn tree(input: &str) -> Tree {
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        Tree::from_program(&program)
+    }
+
+    #[test]
+    fn test_tokens_text() {
+        let (tokens, errors) = tokens("let s = \"a\"; // done");
+        assert_eq!(errors, []);
+        let expected = "\
+1:1      0..3       Let            \"let\"
+1:5      4..5       Identifier     \"s\"
+1:7      6..7       Assign         \"=\"
+1:9      8..11      String         \"a\"
+1:12     11..12     Semicolon      \";\"
+1:14     13..20     Comment        \"// done\"
+1:21     20..20     Eof
+";
+        assert_eq!(tokens_text(&tokens), expected);
+    }
+
+    #[test]
+    fn test_tokens_json() {
+        let (tokens, _) = tokens("x");
+        let expected = r#"[
+  {
+    "type": "Identifier",
+    "literal": "x",
+    "span": {
+      "start": 0,
+      "end": 1,
+      "line": 1,
+      "column": 1
+    }
+  },
+  {
+    "type": "Eof",
+    "literal": "",
+    "span": {
+      "start": 1,
+      "end": 1,
+      "line": 1,
+      "column": 2
+    }
+  }
+]"#;
+        assert_eq!(tokens_json(&tokens).to_string(), expected);
+    }
+
+    #[test]
+    fn test_tokens_with_errors() {
+        let (tokens, errors) = tokens("\"open");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_tokens_text_long_input() {
+        // Each row must be trimmed on its own; trimming the whole output
+        // after every token made this quadratic.
+        let source = "let x = 1;\n".repeat(20_000);
+        let (tokens, _) = tokens(&source);
+        let text = tokens_text(&tokens);
+        assert_eq!(text.lines().count(), 100_001);
+        assert_eq!(
+            text.lines().nth(99_999),
+            Some("20000:10 219998..219999 Semicolon      \";\"")
+        );
+        assert_eq!(text.lines().last(), Some("20001:1  220000..220000 Eof"));
+    }
+
+    #[test]
+    fn test_bytecode_text() {
+        let mut parser = Parser::new("let one = 1; fn(a) { a + one }");
+        let program = parser.parse_program();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        let bytecode = compiler.bytecode();
+        let expected = "\
+0000 OpConstant 0
+0003 OpSetGlobal 0
+0006 OpClosure 1 0
+0010 OpPop
+
+constant 1: fn(a) { (a + one) }
+  0000 OpGetLocal 0
+  0002 OpGetGlobal 0
+  0005 OpAdd
+  0006 OpReturnValue
+";
+        assert_eq!(bytecode_text(&bytecode, 1), expected);
+    }
+
+    #[test]
+    fn test_tree_text() {
+        let input = "let add = fn(a, b) { a + b };\nadd(1, \"two\")[0]";
+        let expected = "\
+Program (1:1)
+  LetStmt (1:1)
+    name: Identifier add (1:5)
+    value: FunctionLiteral (1:11)
+      parameter: Identifier a (1:14)
+      parameter: Identifier b (1:17)
+      body: BlockStatement (1:20)
+        ExpressionStmt (1:22)
+          expression: InfixExpression + (1:22)
+            left: Identifier a (1:22)
+            right: Identifier b (1:26)
+  ExpressionStmt (2:1)
+    expression: IndexExpression (2:1)
+      left: CallExpression (2:1)
+        function: Identifier add (2:1)
+        argument: IntegerLiteral 1 (2:5)
+        argument: StringLiteral \"two\" (2:8)
+      index: IntegerLiteral 0 (2:15)
+";
+        assert_eq!(tree(input).to_text(), expected);
+    }
+
+    #[test]
+    fn test_tree_covers_every_node() {
+        let input = "x += 1; return if (!true) { {1: [2]} } else { -3 };";
+        let expected = "\
+Program (1:1)
+  AssignStatement += (1:1)
+    name: Identifier x (1:1)
+    value: IntegerLiteral 1 (1:6)
+  ReturnStmt (1:9)
+    value: IfExpression (1:16)
+      condition: PrefixExpression ! (1:20)
+        right: Boolean true (1:21)
+      consequence: BlockStatement (1:27)
+        ExpressionStmt (1:29)
+          expression: HashLiteral (1:29)
+            HashPair (1:30)
+              key: IntegerLiteral 1 (1:30)
+              value: ArrayLiteral (1:33)
+                element: IntegerLiteral 2 (1:34)
+      alternative: BlockStatement (1:45)
+        ExpressionStmt (1:47)
+          expression: PrefixExpression - (1:47)
+            right: IntegerLiteral 3 (1:48)
+";
+        assert_eq!(tree(input).to_text(), expected);
+    }
+
+    #[test]
+    fn test_tree_json() {
+        let expected = r#"{
+  "kind": "Program",
+  "span": {
+    "start": 0,
+    "end": 6,
+    "line": 1,
+    "column": 1
+  },
+  "children": [
+    {
+      "kind": "ExpressionStmt",
+      "span": {
+        "start": 0,
+        "end": 6,
+        "line": 1,
+        "column": 1
+      },
+      "children": [
+        {
+          "kind": "StringLiteral",
+          "role": "expression",
+          "value": "a\"b",
+          "span": {
+            "start": 0,
+            "end": 6,
+            "line": 1,
+            "column": 1
+          },
+          "children": []
+        }
+      ]
+    }
+  ]
+}"#;
+        assert_eq!(tree(r#""a\"b""#).to_json().to_string(), expected);
+    }
+}
@@ -0,0 +1,772 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast;
+use crate::builtins;
+use crate::object::{Environment, Function, HashPair, MAX_CALL_DEPTH, Object};
+
+pub fn eval_program(program: &ast::Program, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = Object::Null;
+    for statement in &program.statements {
+        result = eval_statement(statement, env);
+        match result {
+            Object::ReturnValue(value) => return *value,
+            Object::Error(_) => return result,
+            _ => {}
+        }
+    }
+    result
+}
+
+fn eval_block_statement(block: &ast::BlockStatement, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut result = Object::Null;
+    for statement in &block.statements {
+        result = eval_statement(statement, env);
+        if matches!(result, Object::ReturnValue(_) | Object::Error(_)) {
+            return result;
+        }
+    }
+    result
+}
+
+fn eval_statement(statement: &ast::Statement, env: &Rc<RefCell<Environment>>) -> Object {
+    match statement {
+        ast::Statement::LetStmt(let_stmt) => {
+            let value = eval_expression(&let_stmt.value, env);
+            if value.is_error() {
+                return value;
+            }
+            env.borrow_mut().set(&let_stmt.name.value, value);
+            Object::Null
+        }
+        ast::Statement::AssignStatement(assign) => eval_assign_statement(assign, env),
+        ast::Statement::ReturnStmt(return_stmt) => {
+            let value = eval_expression(&return_stmt.return_value, env);
+            if value.is_error() {
+                return value;
+            }
+            Object::ReturnValue(Box::new(value))
+        }
+        ast::Statement::ExpressionStmt(expression_stmt) => {
+            eval_expression(&expression_stmt.expression, env)
+        }
+    }
+}
+
+/// A compound assignment reads its target like any identifier, so an
+/// undeclared target reports `identifier not found`.
+fn eval_assign_statement(assign: &ast::AssignStatement, env: &Rc<RefCell<Environment>>) -> Object {
+    let current = match assign.infix_operator() {
+        Some(operator) => {
+            let current = eval_identifier(&assign.name, env);
+            if current.is_error() {
+                return current;
+            }
+            Some((operator, current))
+        }
+        None => None,
+    };
+    let value = eval_expression(&assign.value, env);
+    if value.is_error() {
+        return value;
+    }
+    let value = match current {
+        Some((operator, current)) => eval_infix_expression(operator, &current, &value),
+        None => value,
+    };
+    if value.is_error() {
+        return value;
+    }
+    if env.borrow_mut().assign(&assign.name.value, value) {
+        Object::Null
+    } else {
+        Object::Error(format!(
+            "assignment to undeclared identifier: {}",
+            assign.name.value
+        ))
+    }
+}
+
+fn eval_expression(expression: &ast::Expression, env: &Rc<RefCell<Environment>>) -> Object {
+    match expression {
+        ast::Expression::Identifier(identifier) => eval_identifier(identifier, env),
+        ast::Expression::IntegerLiteral(integer) => Object::Integer(integer.value),
+        ast::Expression::StringLiteral(string) => Object::String(string.value.clone()),
+        ast::Expression::Boolean(boolean) => Object::Boolean(boolean.value),
+        ast::Expression::PrefixExpression(prefix) => {
+            let right = eval_expression(&prefix.right, env);
+            if right.is_error() {
+                return right;
+            }
+            eval_prefix_expression(&prefix.operator, &right)
+        }
+        ast::Expression::InfixExpression(infix) => {
+            let left = eval_expression(&infix.left, env);
+            if left.is_error() {
+                return left;
+            }
+            let right = eval_expression(&infix.right, env);
+            if right.is_error() {
+                return right;
+            }
+            eval_infix_expression(&infix.operator, &left, &right)
+        }
+        ast::Expression::IfExpression(if_expression) => eval_if_expression(if_expression, env),
+        ast::Expression::FunctionLiteral(function) => Object::Function(Rc::new(Function {
+            parameters: function.parameters.clone(),
+            body: function.body.as_ref().clone(),
+            env: Rc::clone(env),
+        })),
+        ast::Expression::CallExpression(call) => {
+            let function = eval_expression(&call.function, env);
+            if function.is_error() {
+                return function;
+            }
+            let arguments = match eval_expressions(&call.arguments, env) {
+                Ok(arguments) => arguments,
+                Err(error) => return error,
+            };
+            let depth = env.borrow().depth();
+            apply_function(&function, arguments, depth)
+        }
+        ast::Expression::ArrayLiteral(array) => match eval_expressions(&array.elements, env) {
+            Ok(elements) => Object::Array(elements),
+            Err(error) => error,
+        },
+        ast::Expression::IndexExpression(index_expression) => {
+            let left = eval_expression(&index_expression.left, env);
+            if left.is_error() {
+                return left;
+            }
+            let index = eval_expression(&index_expression.index, env);
+            if index.is_error() {
+                return index;
+            }
+            eval_index_expression(&left, &index)
+        }
+        ast::Expression::HashLiteral(hash) => eval_hash_literal(hash, env),
+    }
+}
+
+fn eval_hash_literal(hash: &ast::HashLiteral, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut pairs = HashMap::with_capacity(hash.pairs.len());
+    for (key_expression, value_expression) in &hash.pairs {
+        let key = eval_expression(key_expression, env);
+        if key.is_error() {
+            return key;
+        }
+        let Some(hash_key) = key.hash_key() else {
+            return Object::Error(format!("unusable as hash key: {}", key.type_name()));
+        };
+        let value = eval_expression(value_expression, env);
+        if value.is_error() {
+            return value;
+        }
+        pairs.insert(hash_key, HashPair { key, value });
+    }
+    Object::Hash(pairs)
+}
+
+/// Indexing outside the bounds of an array, or with a key missing from a
+/// hash, evaluates to `null`.
+pub fn eval_index_expression(left: &Object, index: &Object) -> Object {
+    match (left, index) {
+        (Object::Array(elements), Object::Integer(index)) => usize::try_from(*index)
+            .ok()
+            .and_then(|index| elements.get(index))
+            .cloned()
+            .unwrap_or(Object::Null),
+        (Object::Array(_), _) => Object::Error(format!(
+            "array index must be INTEGER, got {}",
+            index.type_name()
+        )),
+        (Object::Hash(pairs), _) => match index.hash_key() {
+            Some(key) => pairs
+                .get(&key)
+                .map_or(Object::Null, |pair| pair.value.clone()),
+            None => Object::Error(format!("unusable as hash key: {}", index.type_name())),
+        },
+        _ => Object::Error(format!(
+            "index operator not supported: {}",
+            left.type_name()
+        )),
+    }
+}
+
+fn eval_expressions(
+    expressions: &[ast::Expression],
+    env: &Rc<RefCell<Environment>>,
+) -> Result<Vec<Object>, Object> {
+    let mut result = Vec::with_capacity(expressions.len());
+    for expression in expressions {
+        let evaluated = eval_expression(expression, env);
+        if evaluated.is_error() {
+            return Err(evaluated);
+        }
+        result.push(evaluated);
+    }
+    Ok(result)
+}
+
+/// Calls `function` from code running `depth` calls deep.
+fn apply_function(function: &Object, arguments: Vec<Object>, depth: usize) -> Object {
+    let function = match function {
+        Object::Function(function) => function,
+        Object::Builtin(builtin) => return (builtin.function)(&arguments),
+        _ => return Object::Error(format!("not a function: {}", function.type_name())),
+    };
+    if function.parameters.len() != arguments.len() {
+        return Object::Error(format!(
+            "wrong number of arguments: want={}, got={}",
+            function.parameters.len(),
+            arguments.len()
+        ));
+    }
+    if depth >= MAX_CALL_DEPTH {
+        return Object::Error("stack overflow".to_string());
+    }
+    let env = Environment::new_enclosed(Rc::clone(&function.env), depth + 1);
+    for (parameter, argument) in function.parameters.iter().zip(arguments) {
+        env.borrow_mut().set(&parameter.value, argument);
+    }
+    let evaluated = eval_block_statement(&function.body, &env);
+    match evaluated {
+        Object::ReturnValue(value) => *value,
+        evaluated => evaluated,
+    }
+}
+
+fn eval_if_expression(if_expression: &ast::IfExpression, env: &Rc<RefCell<Environment>>) -> Object {
+    let condition = eval_expression(&if_expression.condition, env);
+    if condition.is_error() {
+        return condition;
+    }
+    if condition.is_truthy() {
+        eval_block_statement(&if_expression.consequence, env)
+    } else if let Some(alternative) = &if_expression.alternative {
+        eval_block_statement(alternative, env)
+    } else {
+        Object::Null
+    }
+}
+
+/// Builtins are resolved before the environment, so they cannot be shadowed.
+fn eval_identifier(identifier: &ast::Identifier, env: &Rc<RefCell<Environment>>) -> Object {
+    if let Some(builtin) = builtins::lookup(&identifier.value) {
+        return Object::Builtin(builtin);
+    }
+    env.borrow()
+        .get(&identifier.value)
+        .unwrap_or_else(|| Object::Error(format!("identifier not found: {}", identifier.value)))
+}
+
+pub fn eval_prefix_expression(operator: &str, right: &Object) -> Object {
+    match (operator, right) {
+        ("!", _) => Object::Boolean(!right.is_truthy()),
+        ("-", Object::Integer(value)) => value.checked_neg().map_or_else(
+            || Object::Error(format!("integer overflow: -{value}")),
+            Object::Integer,
+        ),
+        _ => Object::Error(format!("unknown operator: {operator}{}", right.type_name())),
+    }
+}
+
+pub fn eval_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+    match (left, right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            eval_integer_infix_expression(operator, *left, *right)
+        }
+        (Object::String(left), Object::String(right)) => {
+            eval_string_infix_expression(operator, left, right)
+        }
+        (Object::Boolean(left), Object::Boolean(right)) => match operator {
+            "==" => Object::Boolean(left == right),
+            "!=" => Object::Boolean(left != right),
+            _ => Object::Error(format!("unknown operator: BOOLEAN {operator} BOOLEAN")),
+        },
+        _ if left.type_name() != right.type_name() => Object::Error(format!(
+            "type mismatch: {} {operator} {}",
+            left.type_name(),
+            right.type_name()
+        )),
+        _ => Object::Error(format!(
+            "unknown operator: {} {operator} {}",
+            left.type_name(),
+            right.type_name()
+        )),
+    }
+}
+
+fn eval_string_infix_expression(operator: &str, left: &str, right: &str) -> Object {
+    match operator {
+        "+" => Object::String([left, right].concat()),
+        "<" => Object::Boolean(left < right),
+        "<=" => Object::Boolean(left <= right),
+        ">" => Object::Boolean(left > right),
+        ">=" => Object::Boolean(left >= right),
+        "==" => Object::Boolean(left == right),
+        "!=" => Object::Boolean(left != right),
+        _ => Object::Error(format!("unknown operator: STRING {operator} STRING")),
+    }
+}
+
+fn eval_integer_infix_expression(operator: &str, left: i64, right: i64) -> Object {
+    let arithmetic = match operator {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" if right == 0 => return Object::Error("division by zero".to_string()),
+        "/" => left.checked_div(right),
+        "<" => return Object::Boolean(left < right),
+        "<=" => return Object::Boolean(left <= right),
+        ">" => return Object::Boolean(left > right),
+        ">=" => return Object::Boolean(left >= right),
+        "==" => return Object::Boolean(left == right),
+        "!=" => return Object::Boolean(left != right),
+        _ => {
+            return Object::Error(format!("unknown operator: INTEGER {operator} INTEGER"));
+        }
+    };
+    arithmetic.map_or_else(
+        || Object::Error(format!("integer overflow: {left} {operator} {right}")),
+        Object::Integer,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::object::HashKey;
+    use crate::parser::Parser;
+
+    fn eval(input: &str) -> Object {
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        eval_program(&program, &Environment::new())
+    }
+
+    #[test]
+    fn test_eval_integer_expression() {
+        let test_cases = [
+            ("5", 5),
+            ("10", 10),
+            ("-5", -5),
+            ("-10", -10),
+            ("5 + 5 + 5 + 5 - 10", 10),
+            ("2 * 2 * 2 * 2 * 2", 32),
+            ("-50 + 100 + -50", 0),
+            ("5 * 2 + 10", 20),
+            ("5 + 2 * 10", 25),
+            ("20 + 2 * -10", 0),
+            ("50 / 2 * 2 + 10", 60),
+            ("2 * (5 + 10)", 30),
+            ("3 * 3 * 3 + 10", 37),
+            ("3 * (3 * 3) + 10", 37),
+            ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Integer(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_eval_boolean_expression() {
+        let test_cases = [
+            ("true", true),
+            ("false", false),
+            ("1 < 2", true),
+            ("1 > 2", false),
+            ("1 <= 1", true),
+            ("2 >= 3", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("true == true", true),
+            ("true != false", true),
+            ("(1 < 2) == true", true),
+            ("(1 > 2) == true", false),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Boolean(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_bang_operator() {
+        let test_cases = [
+            ("!true", false),
+            ("!false", true),
+            ("!5", false),
+            ("!!true", true),
+            ("!!false", false),
+            ("!!5", true),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Boolean(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_if_else_expressions() {
+        let test_cases = [
+            ("if (true) { 10 }", Object::Integer(10)),
+            ("if (false) { 10 }", Object::Null),
+            ("if (1) { 10 }", Object::Integer(10)),
+            ("if (1 < 2) { 10 }", Object::Integer(10)),
+            ("if (1 > 2) { 10 }", Object::Null),
+            ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20)),
+            ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10)),
+            (
+                "if (1 > 2) { 10 } else if (1 == 1) { 30 } else { 20 }",
+                Object::Integer(30),
+            ),
+            (
+                "if (1 > 2) { 10 } else if (1 == 2) { 30 } else { 20 }",
+                Object::Integer(20),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_function_object() {
+        let Object::Function(function) = eval("fn(x) { x + 2; };") else {
+            panic!("Expected Function");
+        };
+        assert_eq!(function.parameters.len(), 1);
+        assert_eq!(function.parameters[0].value, "x");
+        assert_eq!(function.body.to_string(), "{ (x + 2) }");
+    }
+
+    #[test]
+    fn test_function_application() {
+        let test_cases = [
+            ("fn(x) { x; }(5)", 5),
+            ("fn(x) { x * 2; }(5)", 10),
+            ("fn(x, y) { x + y; }(5, 5)", 10),
+            ("fn(x) { return x; }(5)", 5),
+            ("fn(x) { if (x > 1) { return 1; } 2 }(5)", 1),
+            ("let identity = fn(x) { x; }; identity(5);", 5),
+            ("let add = fn(a, b) { a + b }; add(1, 2)", 3),
+            ("fn(x, y) { x + y; }(5 + 5, fn(x) { x }(10))", 20),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Integer(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let input = "fn(x) { fn(y) { x + y } }(2)(3)";
+        assert_eq!(eval(input), Object::Integer(5));
+        let input = "
+        let newAdder = fn(x) { fn(y) { x + y } };
+        let addTwo = newAdder(2);
+        addTwo(3);
+        ";
+        assert_eq!(eval(input), Object::Integer(5));
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let test_cases = [
+            ("let a = 5; a;", 5),
+            ("let a = 5 * 5; a;", 25),
+            ("let a = 5; let b = a; b;", 5),
+            ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Integer(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_assign_statements() {
+        let test_cases = [
+            ("let a = 5; a = 6; a", Object::Integer(6)),
+            ("let a = 5; a += 2; a", Object::Integer(7)),
+            ("let a = 5; a -= 2; a", Object::Integer(3)),
+            ("let a = 5; a *= 2; a", Object::Integer(10)),
+            ("let a = 5; a /= 2; a", Object::Integer(2)),
+            (
+                r#"let s = "a"; s += "b"; s"#,
+                Object::String("ab".to_string()),
+            ),
+            ("let a = 5; a = 6;", Object::Null),
+            (
+                "let count = 0; let inc = fn() { count += 1 }; inc(); inc(); count",
+                Object::Integer(2),
+            ),
+            (
+                "let a = 1; let f = fn() { let a = 2; a = 3; a }; f() + a",
+                Object::Integer(4),
+            ),
+            (
+                "let counter = fn() { let n = 0; fn() { n += 1; n } }; let c = counter(); c(); c()",
+                Object::Integer(2),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_return_statements() {
+        let test_cases = [
+            ("return 10;", 10),
+            ("return 10; 9;", 10),
+            ("return 2 * 5; 9;", 10),
+            ("9; return 2 * 5; 9;", 10),
+            ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+            (
+                "let fib = fn(n) { if (n < 2) { return n; } fib(n - 1) + fib(n - 2) }; fib(10)",
+                55,
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), Object::Integer(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_string_expressions() {
+        let test_cases = [
+            (
+                r#""Hello World!""#,
+                Object::String("Hello World!".to_string()),
+            ),
+            (
+                r#""Hello" + " " + "World!""#,
+                Object::String("Hello World!".to_string()),
+            ),
+            (r#""a\tb""#, Object::String("a\tb".to_string())),
+            (r#""abc" == "abc""#, Object::Boolean(true)),
+            (r#""abc" != "abd""#, Object::Boolean(true)),
+            (r#""abc" < "abd""#, Object::Boolean(true)),
+            (r#""b" >= "abc""#, Object::Boolean(true)),
+            (
+                r#"let greet = fn(name) { "Hi " + name }; greet("Monkey")"#,
+                Object::String("Hi Monkey".to_string()),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_array_literals() {
+        let test_cases = [
+            (
+                "[1, 2 * 2, 3 + 3]",
+                Object::Array(vec![
+                    Object::Integer(1),
+                    Object::Integer(4),
+                    Object::Integer(6),
+                ]),
+            ),
+            (
+                r#"[1, "two", true, [fn(x) { x }(3)]]"#,
+                Object::Array(vec![
+                    Object::Integer(1),
+                    Object::String("two".to_string()),
+                    Object::Boolean(true),
+                    Object::Array(vec![Object::Integer(3)]),
+                ]),
+            ),
+            ("[]", Object::Array(vec![])),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_array_index_expressions() {
+        let test_cases = [
+            ("[1, 2, 3][0]", Object::Integer(1)),
+            ("[1, 2, 3][1]", Object::Integer(2)),
+            ("[1, 2, 3][2]", Object::Integer(3)),
+            ("let i = 0; [1][i];", Object::Integer(1)),
+            ("[1, 2, 3][1 + 1];", Object::Integer(3)),
+            ("let myArray = [1, 2, 3]; myArray[2];", Object::Integer(3)),
+            (
+                "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];",
+                Object::Integer(6),
+            ),
+            ("[[1, 2], [3]][0][1]", Object::Integer(2)),
+            ("[1, 2, 3][3]", Object::Null),
+            ("[1, 2, 3][-1]", Object::Null),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_hash_literals() {
+        let input = r#"
+        let two = "two";
+        {
+            "one": 10 - 9,
+            two: 1 + 1,
+            "thr" + "ee": 6 / 2,
+            4: 4,
+            true: 5,
+            false: 6
+        }
+        "#;
+        let Object::Hash(pairs) = eval(input) else {
+            panic!("Expected Hash");
+        };
+        let expected = [
+            (HashKey::String("one".to_string()), 1),
+            (HashKey::String("two".to_string()), 2),
+            (HashKey::String("three".to_string()), 3),
+            (HashKey::Integer(4), 4),
+            (HashKey::Boolean(true), 5),
+            (HashKey::Boolean(false), 6),
+        ];
+        assert_eq!(pairs.len(), expected.len());
+        for (key, value) in expected {
+            assert_eq!(pairs[&key].value, Object::Integer(value), "key: {key:?}");
+        }
+    }
+
+    #[test]
+    fn test_hash_index_expressions() {
+        let test_cases = [
+            (r#"{"foo": 5}["foo"]"#, Object::Integer(5)),
+            (r#"{"foo": 5}["bar"]"#, Object::Null),
+            (r#"let key = "foo"; {"foo": 5}[key]"#, Object::Integer(5)),
+            (r#"{}["foo"]"#, Object::Null),
+            ("{5: 5}[5]", Object::Integer(5)),
+            ("{true: 5}[true]", Object::Integer(5)),
+            ("{false: 5}[false]", Object::Integer(5)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_hash_display() {
+        let input = r#"{"b": 2, 1: [true], "a": "x"}"#;
+        assert_eq!(eval(input).to_string(), "{1: [true], a: x, b: 2}");
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let test_cases = [
+            (r#"len("")"#, Object::Integer(0)),
+            (r#"len("four")"#, Object::Integer(4)),
+            (r#"len("héllo")"#, Object::Integer(5)),
+            ("len([1, 2, 3])", Object::Integer(3)),
+            (r#"len({"a": 1})"#, Object::Integer(1)),
+            ("first([1, 2, 3])", Object::Integer(1)),
+            ("first([])", Object::Null),
+            ("last([1, 2, 3])", Object::Integer(3)),
+            ("last([])", Object::Null),
+            (
+                "rest([1, 2, 3])",
+                Object::Array(vec![Object::Integer(2), Object::Integer(3)]),
+            ),
+            ("rest([1])", Object::Array(vec![])),
+            ("rest([])", Object::Null),
+            ("push([], 1)", Object::Array(vec![Object::Integer(1)])),
+            (
+                "let a = [1]; push(a, 2); a",
+                Object::Array(vec![Object::Integer(1)]),
+            ),
+            ("puts()", Object::Null),
+            ("let len = fn(x) { 0 }; len([1])", Object::Integer(1)),
+            (
+                "let map = fn(f, xs) { if (len(xs) == 0) { [] } else { push(map(f, rest(xs)), f(first(xs))) } }; map(fn(x) { x * 2 }, [1, 2])",
+                Object::Array(vec![Object::Integer(4), Object::Integer(2)]),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(eval(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_builtin_errors() {
+        let test_cases = [
+            ("len(1)", "argument to `len` not supported, got INTEGER"),
+            (
+                r#"len("one", "two")"#,
+                "wrong number of arguments: want=1, got=2",
+            ),
+            ("len()", "wrong number of arguments: want=1, got=0"),
+            ("first(1)", "argument to `first` must be ARRAY, got INTEGER"),
+            (
+                r#"last("a")"#,
+                "argument to `last` must be ARRAY, got STRING",
+            ),
+            (
+                "rest(true)",
+                "argument to `rest` must be ARRAY, got BOOLEAN",
+            ),
+            (
+                "push(1, 1)",
+                "argument to `push` must be ARRAY, got INTEGER",
+            ),
+            ("push([])", "wrong number of arguments: want=2, got=1"),
+            ("len(foo)", "identifier not found: foo"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(
+                eval(input),
+                Object::Error(expected.to_string()),
+                "input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_handling() {
+        let test_cases = [
+            ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
+            ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+            ("-true", "unknown operator: -BOOLEAN"),
+            ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
+            ("5; true + false; 5", "unknown operator: BOOLEAN + BOOLEAN"),
+            ("1 / 0", "division by zero"),
+            (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+            (r#""Hello" + 1"#, "type mismatch: STRING + INTEGER"),
+            ("1[0]", "index operator not supported: INTEGER"),
+            ("[1][true]", "array index must be INTEGER, got BOOLEAN"),
+            (
+                r#"{"name": "Monkey"}[fn(x) { x }];"#,
+                "unusable as hash key: FUNCTION",
+            ),
+            ("{[1]: 2}", "unusable as hash key: ARRAY"),
+            ("5(1)", "not a function: INTEGER"),
+            ("fn(x) { x }()", "wrong number of arguments: want=1, got=0"),
+            (
+                "if (10 > 1) { true + false; }",
+                "unknown operator: BOOLEAN + BOOLEAN",
+            ),
+            (
+                "9223372036854775807 + 1",
+                "integer overflow: 9223372036854775807 + 1",
+            ),
+            ("foobar", "identifier not found: foobar"),
+            ("x = 1", "assignment to undeclared identifier: x"),
+            ("fn() { x = 1 }()", "assignment to undeclared identifier: x"),
+            ("x += 1", "identifier not found: x"),
+            ("let x = 1; x += true", "type mismatch: INTEGER + BOOLEAN"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(
+                eval(input),
+                Object::Error(expected.to_string()),
+                "input: {input}"
+            );
+        }
+    }
+}
@@ -0,0 +1,853 @@
+//! A width-aware pretty-printer for Monkey source.
+//!
+//! The AST is first lowered to a `Doc`: text and potential line breaks
+//! arranged in groups, where each group is laid out on one line when it fits
+//! in the remaining width and broken across lines otherwise, after Wadler's
+//! "A prettier printer". Comments are not part of the AST, so they are lexed
+//! separately and reattached by source position: before the statement, list
+//! item or operand they precede, and on the line they were written on when
+//! they follow other code.
+
+use crate::ast::{self, Node};
+use crate::lexer::Lexer;
+use crate::parser::{ParseError, Parser};
+use crate::token::{Token, TokenType};
+
+pub const DEFAULT_WIDTH: usize = 80;
+const INDENT: usize = 4;
+
+/// Formats `source`, failing if it does not parse.
+pub fn format_source(source: &str, width: usize) -> Result<String, Vec<ParseError>> {
+    let mut parser = Parser::new(source);
+    let program = parser.parse_program();
+    if !parser.errors.is_empty() {
+        return Err(parser.errors);
+    }
+    let mut lowering = Lowering::new(source);
+    let docs = lowering.statements(&program.statements, source.len(), true);
+    let output = print(&Doc::Concat(docs), width);
+    let output = output.trim_end();
+    Ok(if output.is_empty() {
+        String::new()
+    } else {
+        format!("{output}\n")
+    })
+}
+
+#[derive(Debug)]
+enum Doc {
+    Text(String),
+    /// A space when the enclosing group is flat, a newline when broken.
+    Line,
+    /// Nothing when the enclosing group is flat, a newline when broken.
+    SoftLine,
+    /// Always a newline. Breaks the enclosing group.
+    HardLine,
+    /// Breaks the enclosing group without printing anything.
+    BreakParent,
+    /// Indents line breaks within, when the enclosing group is broken.
+    Nest(Vec<Doc>),
+    Concat(Vec<Doc>),
+    Group {
+        docs: Vec<Doc>,
+        broken: bool,
+    },
+}
+
+fn text(text: impl Into<String>) -> Doc {
+    Doc::Text(text.into())
+}
+
+/// Groups `docs`, breaking the group up front when it contains a hard break
+/// outside of any nested group. Breaks do not propagate out of nested groups,
+/// so a function literal argument can keep its call on one line:
+///
+/// ```text
+/// map(xs, fn(x) {
+///     x * 2
+/// });
+/// ```
+fn group(docs: Vec<Doc>) -> Doc {
+    let broken = docs.iter().any(forces_break);
+    Doc::Group { docs, broken }
+}
+
+fn forces_break(doc: &Doc) -> bool {
+    match doc {
+        Doc::HardLine | Doc::BreakParent => true,
+        Doc::Text(text) => text.contains('\n'),
+        Doc::Nest(docs) | Doc::Concat(docs) => docs.iter().any(forces_break),
+        Doc::Line | Doc::SoftLine | Doc::Group { .. } => false,
+    }
+}
+
+/// Whether `doc` or any group nested in it is broken up front.
+fn contains_break(doc: &Doc) -> bool {
+    match doc {
+        Doc::Group { docs, broken } => *broken || docs.iter().any(contains_break),
+        Doc::Nest(docs) | Doc::Concat(docs) => docs.iter().any(contains_break),
+        doc => forces_break(doc),
+    }
+}
+
+/// The first character `doc` prints, if any.
+fn first_char(doc: &Doc) -> Option<char> {
+    match doc {
+        Doc::Text(text) => text.chars().next(),
+        Doc::Nest(docs) | Doc::Concat(docs) | Doc::Group { docs, .. } => {
+            docs.iter().find_map(first_char)
+        }
+        Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::BreakParent => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+fn print(doc: &Doc, width: usize) -> String {
+    let mut output = String::new();
+    let mut column = 0;
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                output.push_str(text);
+                column = match text.rfind('\n') {
+                    Some(newline) => text[newline + 1..].chars().count(),
+                    None => column + text.chars().count(),
+                };
+            }
+            Doc::Line if mode == Mode::Flat => {
+                output.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                let trimmed = output.trim_end_matches(' ').len();
+                output.truncate(trimmed);
+                output.push('\n');
+                output.push_str(&" ".repeat(indent));
+                column = indent;
+            }
+            Doc::BreakParent => {}
+            Doc::Nest(docs) => {
+                let indent = if mode == Mode::Break {
+                    indent + INDENT
+                } else {
+                    indent
+                };
+                stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Group { docs, broken } => {
+                let mode = if *broken {
+                    Mode::Break
+                } else if mode == Mode::Flat || fits(width.saturating_sub(column), docs, &stack) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+        }
+    }
+    output
+}
+
+/// Whether `docs` laid out flat, followed by the rest of the document, fit
+/// in `remaining` columns up to the next line break.
+fn fits(mut remaining: usize, docs: &[Doc], rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut pending: Vec<(Mode, &Doc)> = docs.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = rest.iter().rev().map(|&(_, mode, doc)| (mode, doc));
+    while let Some((mode, doc)) = pending.pop().or_else(|| rest.next()) {
+        let width = match doc {
+            Doc::Text(text) => match text.find('\n') {
+                Some(newline) => return text[..newline].chars().count() <= remaining,
+                None => text.chars().count(),
+            },
+            Doc::Line if mode == Mode::Flat => 1,
+            Doc::SoftLine if mode == Mode::Flat => 0,
+            Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+            Doc::BreakParent => 0,
+            Doc::Nest(docs) | Doc::Concat(docs) => {
+                pending.extend(docs.iter().rev().map(|doc| (mode, doc)));
+                0
+            }
+            Doc::Group { docs, broken } => {
+                let mode = if *broken { Mode::Break } else { mode };
+                pending.extend(docs.iter().rev().map(|doc| (mode, doc)));
+                0
+            }
+        };
+        match remaining.checked_sub(width) {
+            Some(left) => remaining = left,
+            None => return false,
+        }
+    }
+    true
+}
+
+fn precedence(operator: &str) -> u8 {
+    match operator {
+        "==" | "!=" => 1,
+        "<" | ">" | "<=" | ">=" => 2,
+        "+" | "-" => 3,
+        _ => 4,
+    }
+}
+
+fn infix_precedence(expression: &ast::Expression) -> Option<u8> {
+    match expression {
+        ast::Expression::InfixExpression(infix) => Some(precedence(&infix.operator)),
+        _ => None,
+    }
+}
+
+fn is_operator(expression: &ast::Expression) -> bool {
+    matches!(
+        expression,
+        ast::Expression::PrefixExpression(_) | ast::Expression::InfixExpression(_)
+    )
+}
+
+/// The `if` in an `else if` chain, which the parser wraps in a block.
+fn else_if(block: &ast::BlockStatement) -> Option<&ast::IfExpression> {
+    if !block.token.is_type(TokenType::Else) {
+        return None;
+    }
+    match block.statements.as_slice() {
+        [ast::Statement::ExpressionStmt(statement)] => match &statement.expression {
+            ast::Expression::IfExpression(if_expression) => Some(if_expression),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Lowers the AST to a `Doc`, consuming comments in source order.
+struct Lowering<'a> {
+    source: &'a str,
+    comments: Vec<Token>,
+    next_comment: usize,
+}
+
+impl Lowering<'_> {
+    fn new(source: &str) -> Lowering<'_> {
+        let mut lexer = Lexer::new(source).with_trivia();
+        let mut comments = vec![];
+        loop {
+            let mut token = lexer.next_token();
+            comments.append(&mut token.leading_trivia);
+            if token.is_type(TokenType::Eof) {
+                break;
+            }
+        }
+        Lowering {
+            source,
+            comments,
+            next_comment: 0,
+        }
+    }
+
+    /// Takes the next comment if it starts before `offset`.
+    fn comment_before(&mut self, offset: usize) -> Option<Token> {
+        let comment = self.comments.get(self.next_comment)?;
+        if comment.span.start >= offset {
+            return None;
+        }
+        self.next_comment += 1;
+        Some(comment.clone())
+    }
+
+    /// Whether `comment` is the first thing on its line in the source.
+    fn starts_line(&self, comment: &Token) -> bool {
+        let before = &self.source[..comment.span.start];
+        before[before.trim_end().len()..].contains('\n')
+    }
+
+    fn comment(comment: &Token) -> Doc {
+        if comment.literal.starts_with("//") {
+            Doc::Concat(vec![text(&comment.literal), Doc::BreakParent])
+        } else {
+            text(&comment.literal)
+        }
+    }
+
+    /// Separates the item starting at `start` from the one ending at
+    /// `previous_end`, keeping one blank line if the source had any.
+    fn separate(&self, docs: &mut Vec<Doc>, previous_end: Option<usize>, start: usize) {
+        if let Some(previous_end) = previous_end {
+            docs.push(Doc::HardLine);
+            // Comments hoisted out of a statement end after it starts.
+            let gap = self.source.get(previous_end..start).unwrap_or_default();
+            if gap.matches('\n').count() > 1 {
+                docs.push(Doc::HardLine);
+            }
+        }
+    }
+
+    /// Lowers a statement list, with the comments before `end`, into lines
+    /// separated by hard breaks. The final expression statement of a block
+    /// is its value and is left without a semicolon.
+    fn statements(
+        &mut self,
+        statements: &[ast::Statement],
+        end: usize,
+        top_level: bool,
+    ) -> Vec<Doc> {
+        let mut docs = vec![];
+        let mut previous_end = None;
+        let mut open_if = None;
+        for (i, statement) in statements.iter().enumerate() {
+            let span = statement.span();
+            while let Some(comment) = self.comment_before(span.start) {
+                self.separate(&mut docs, previous_end, comment.span.start);
+                docs.push(Self::comment(&comment));
+                previous_end = Some(comment.span.end);
+            }
+            let is_if = matches!(
+                statement,
+                ast::Statement::ExpressionStmt(ast::ExpressionStmt {
+                    expression: ast::Expression::IfExpression(_),
+                    ..
+                })
+            );
+            let is_last = i + 1 == statements.len();
+            let doc = self.statement(statement, (top_level || !is_last) && !is_if);
+            while let Some(comment) = self.comment_before(span.end) {
+                self.separate(&mut docs, previous_end, comment.span.start);
+                docs.push(Self::comment(&comment));
+                previous_end = Some(comment.span.end);
+            }
+            // An `if` statement needs no semicolon unless the next statement
+            // would otherwise continue it as an operator, call or index.
+            if let Some(index) = open_if.take()
+                && matches!(first_char(&doc), Some('(' | '[' | '-'))
+                && let Doc::Concat(docs) = &mut docs[index]
+            {
+                docs.push(text(";"));
+            }
+            self.separate(&mut docs, previous_end, span.start);
+            if is_if {
+                open_if = Some(docs.len());
+            }
+            docs.push(doc);
+            previous_end = Some(span.end);
+            if let Some(comment) = self.comments.get(self.next_comment)
+                && comment.span.start < end
+                && !self.source[span.end..comment.span.start].contains('\n')
+            {
+                docs.push(text(" "));
+                docs.push(Self::comment(comment));
+                previous_end = Some(comment.span.end);
+                self.next_comment += 1;
+            }
+        }
+        while let Some(comment) = self.comment_before(end) {
+            self.separate(&mut docs, previous_end, comment.span.start);
+            docs.push(Self::comment(&comment));
+            previous_end = Some(comment.span.end);
+        }
+        docs
+    }
+
+    fn statement(&mut self, statement: &ast::Statement, semicolon: bool) -> Doc {
+        match statement {
+            ast::Statement::LetStmt(let_stmt) => Doc::Concat(vec![
+                text(format!("let {} = ", let_stmt.name)),
+                self.expression(&let_stmt.value),
+                text(";"),
+            ]),
+            ast::Statement::AssignStatement(assign) => Doc::Concat(vec![
+                text(format!("{} {} ", assign.name, assign.operator)),
+                self.expression(&assign.value),
+                text(";"),
+            ]),
+            ast::Statement::ReturnStmt(return_stmt) => Doc::Concat(vec![
+                text("return "),
+                self.expression(&return_stmt.return_value),
+                text(";"),
+            ]),
+            ast::Statement::ExpressionStmt(expression_stmt) => {
+                let mut docs = vec![self.expression(&expression_stmt.expression)];
+                if semicolon {
+                    docs.push(text(";"));
+                }
+                Doc::Concat(docs)
+            }
+        }
+    }
+
+    /// Lowers a block as its own group. Unlike other groups, a block breaks
+    /// whenever anything inside it does, so a broken `if` or function is
+    /// never squeezed onto the line of an enclosing block's brace.
+    fn block(&mut self, block: &ast::BlockStatement) -> Doc {
+        match self.block_contents(block) {
+            Doc::Concat(docs) => {
+                let broken = docs.iter().any(contains_break);
+                Doc::Group { docs, broken }
+            }
+            empty => empty,
+        }
+    }
+
+    /// A block's braces and statements, left ungrouped so that an `if`
+    /// chain can break all of its blocks together.
+    fn block_contents(&mut self, block: &ast::BlockStatement) -> Doc {
+        let docs = self.statements(&block.statements, block.span.end, false);
+        if docs.is_empty() {
+            return text("{}");
+        }
+        Doc::Concat(vec![
+            text("{"),
+            Doc::Nest(vec![Doc::Line, Doc::Concat(docs)]),
+            Doc::Line,
+            text("}"),
+        ])
+    }
+
+    /// Lowers `expression`, wrapped in parentheses when `parenthesize` is set
+    /// because the parser would otherwise group it differently.
+    fn operand(&mut self, expression: &ast::Expression, parenthesize: bool) -> Doc {
+        let doc = self.expression(expression);
+        if parenthesize {
+            Doc::Concat(vec![text("("), doc, text(")")])
+        } else {
+            doc
+        }
+    }
+
+    /// A delimited, comma-separated list that puts each item on its own line
+    /// when it does not fit. Items are lowered by `lower`, in order, so that
+    /// the comments between them, up to `end`, keep their place.
+    fn list<T>(
+        &mut self,
+        open: &str,
+        items: &[T],
+        close: &str,
+        end: usize,
+        start: impl Fn(&T) -> usize,
+        mut lower: impl FnMut(&mut Self, &T) -> Doc,
+    ) -> Doc {
+        let mut inner = vec![Doc::SoftLine];
+        for (i, item) in items.iter().enumerate() {
+            let separator = (i > 0).then(|| {
+                inner.push(text(","));
+                Doc::Line
+            });
+            let separator = self.list_comments(&mut inner, start(item), separator);
+            inner.extend(separator);
+            inner.push(lower(self, item));
+        }
+        let separator = (!items.is_empty()).then_some(Doc::Line);
+        self.list_comments(&mut inner, end, separator);
+        if inner.len() == 1 {
+            return text(format!("{open}{close}"));
+        }
+        group(vec![
+            text(open),
+            Doc::Nest(inner),
+            Doc::SoftLine,
+            text(close),
+        ])
+    }
+
+    /// Lowers the comments before `offset` between the items of a list,
+    /// where `separator` is the break owed before the next item. A comment on
+    /// the line of the item before it stays there. Returns what separates the
+    /// last comment from the next item.
+    fn list_comments(
+        &mut self,
+        docs: &mut Vec<Doc>,
+        offset: usize,
+        mut separator: Option<Doc>,
+    ) -> Option<Doc> {
+        while let Some(comment) = self.comment_before(offset) {
+            match separator.take() {
+                Some(Doc::Line) if !self.starts_line(&comment) => {
+                    docs.push(text(" "));
+                    separator = Some(Doc::Line);
+                }
+                owed => docs.extend(owed),
+            }
+            docs.push(Self::comment(&comment));
+            if comment.literal.starts_with("//") {
+                separator = Some(Doc::Line);
+            } else if separator.is_none() {
+                separator = Some(text(" "));
+            }
+        }
+        separator
+    }
+
+    fn if_expression(&mut self, if_expression: &ast::IfExpression) -> Doc {
+        let mut docs = vec![];
+        self.if_chain(if_expression, &mut docs);
+        group(docs)
+    }
+
+    fn if_chain(&mut self, if_expression: &ast::IfExpression, docs: &mut Vec<Doc>) {
+        docs.push(text("if ("));
+        docs.push(self.expression(&if_expression.condition));
+        docs.push(text(") "));
+        docs.push(self.block_contents(&if_expression.consequence));
+        if let Some(alternative) = &if_expression.alternative {
+            let mut separator = text(" ");
+            while let Some(comment) = self.comment_before(alternative.span.start) {
+                docs.push(if self.starts_line(&comment) {
+                    Doc::HardLine
+                } else {
+                    text(" ")
+                });
+                docs.push(Self::comment(&comment));
+                separator = if comment.literal.starts_with("//") {
+                    Doc::HardLine
+                } else {
+                    text(" ")
+                };
+            }
+            docs.push(separator);
+            docs.push(text("else "));
+            match else_if(alternative) {
+                Some(nested) => self.if_chain(nested, docs),
+                None => docs.push(self.block_contents(alternative)),
+            }
+        }
+    }
+
+    /// Lowers `expression` after the comments that precede it. A comment
+    /// written on a line of its own keeps one; the others stay on the line
+    /// of the code before them.
+    fn expression(&mut self, expression: &ast::Expression) -> Doc {
+        let mut comments = vec![];
+        let mut line_start = false;
+        while let Some(comment) = self.comment_before(expression.span().start) {
+            if self.starts_line(&comment) && !line_start {
+                comments.push(Doc::HardLine);
+            }
+            comments.push(Self::comment(&comment));
+            line_start = comment.literal.starts_with("//");
+            comments.push(if line_start { Doc::HardLine } else { text(" ") });
+        }
+        let doc = self.bare_expression(expression);
+        if comments.is_empty() {
+            doc
+        } else {
+            Doc::Concat(vec![Doc::Nest(comments), doc])
+        }
+    }
+
+    fn bare_expression(&mut self, expression: &ast::Expression) -> Doc {
+        match expression {
+            ast::Expression::Identifier(_)
+            | ast::Expression::IntegerLiteral(_)
+            | ast::Expression::StringLiteral(_)
+            | ast::Expression::Boolean(_) => text(expression.to_string()),
+            ast::Expression::PrefixExpression(prefix) => Doc::Concat(vec![
+                text(&prefix.operator),
+                self.operand(&prefix.right, infix_precedence(&prefix.right).is_some()),
+            ]),
+            ast::Expression::InfixExpression(infix) => {
+                let precedence = precedence(&infix.operator);
+                let left = infix_precedence(&infix.left).is_some_and(|left| left < precedence);
+                let right = infix_precedence(&infix.right).is_some_and(|right| right <= precedence);
+                Doc::Concat(vec![
+                    self.operand(&infix.left, left),
+                    text(format!(" {} ", infix.operator)),
+                    self.operand(&infix.right, right),
+                ])
+            }
+            ast::Expression::IfExpression(if_expression) => self.if_expression(if_expression),
+            ast::Expression::FunctionLiteral(function) => {
+                let parameters = self.list(
+                    "(",
+                    &function.parameters,
+                    ")",
+                    function.body.span.start,
+                    |parameter| parameter.span().start,
+                    |_, parameter| text(&parameter.value),
+                );
+                Doc::Concat(vec![
+                    text("fn"),
+                    parameters,
+                    text(" "),
+                    self.block(&function.body),
+                ])
+            }
+            ast::Expression::CallExpression(call) => {
+                let function = self.operand(&call.function, is_operator(&call.function));
+                let arguments = self.list(
+                    "(",
+                    &call.arguments,
+                    ")",
+                    call.span.end,
+                    |argument| argument.span().start,
+                    Self::expression,
+                );
+                Doc::Concat(vec![function, arguments])
+            }
+            ast::Expression::ArrayLiteral(array) => self.list(
+                "[",
+                &array.elements,
+                "]",
+                array.span.end,
+                |element| element.span().start,
+                Self::expression,
+            ),
+            ast::Expression::IndexExpression(index) => Doc::Concat(vec![
+                self.operand(&index.left, is_operator(&index.left)),
+                text("["),
+                self.expression(&index.index),
+                text("]"),
+            ]),
+            ast::Expression::HashLiteral(hash) => self.list(
+                "{",
+                &hash.pairs,
+                "}",
+                hash.span.end,
+                |(key, _)| key.span().start,
+                |lowering, (key, value)| {
+                    Doc::Concat(vec![
+                        lowering.expression(key),
+                        text(": "),
+                        lowering.expression(value),
+                    ])
+                },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::canonical;
+
+    fn format(input: &str) -> String {
+        format_source(input, DEFAULT_WIDTH).unwrap()
+    }
+
+    fn assert_same_program(input: &str, formatted: &str) {
+        let canonical = |source: &str| {
+            let mut parser = Parser::new(source);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{source}: {:?}", parser.errors);
+            canonical::program(&program)
+        };
+        assert_eq!(canonical(input), canonical(formatted), "input: {input}");
+    }
+
+    /// Formatting must not change the program and must be a fixed point.
+    fn assert_stable(input: &str, formatted: &str) {
+        assert_same_program(input, formatted);
+        assert_eq!(format(formatted), formatted, "input: {input}");
+    }
+
+    #[test]
+    fn test_format() {
+        let test_cases = [
+            ("", ""),
+            ("let x=1+2*3", "let x = 1 + 2 * 3;\n"),
+            ("x+=1 ;  return x", "x += 1;\nreturn x;\n"),
+            ("puts( \"hi\\n\" )", "puts(\"hi\\n\");\n"),
+            ("(1 + 2) * 3 - (4 - 5)", "(1 + 2) * 3 - (4 - 5);\n"),
+            ("1 - (2 + 3) == (1 == 2)", "1 - (2 + 3) == (1 == 2);\n"),
+            ("-(a + b) * !c", "-(a + b) * !c;\n"),
+            ("(-f)(x)[0]", "(-f)(x)[0];\n"),
+            ("fn(a,b){a+b}(1,2)", "fn(a, b) { a + b }(1, 2);\n"),
+            ("let f = fn() {}", "let f = fn() {};\n"),
+            ("[1,2][0]; {}; {\"a\":1,}", "[1, 2][0];\n{};\n{\"a\": 1};\n"),
+            ("if(x<y){x}else{y}", "if (x < y) { x } else { y }\n"),
+            (
+                "if (a) { 1 } else if (b) { 2 } else { 3 }",
+                "if (a) { 1 } else if (b) { 2 } else { 3 }\n",
+            ),
+            (
+                "let f = fn(x) { let y = x * 2; y + 1 };",
+                "let f = fn(x) {\n    let y = x * 2;\n    y + 1\n};\n",
+            ),
+            (
+                "fn(x) { puts(x); x; }",
+                "fn(x) {\n    puts(x);\n    x\n};\n",
+            ),
+            (
+                "let a = 1;\n\n\n\nlet b = 2;\nlet c = 3;",
+                "let a = 1;\n\nlet b = 2;\nlet c = 3;\n",
+            ),
+        ];
+        for (input, expected) in test_cases {
+            let formatted = format(input);
+            assert_eq!(formatted, expected, "input: {input}");
+            assert_stable(input, &formatted);
+        }
+    }
+
+    #[test]
+    fn test_if_statement_semicolons() {
+        let test_cases = [
+            ("if (a) { 1 }; b", "if (a) { 1 }\nb;\n"),
+            ("if (a) { 1 }; -b", "if (a) { 1 };\n-b;\n"),
+            ("if (a) { 1 }; (b + c) * d", "if (a) { 1 };\n(b + c) * d;\n"),
+            ("if (a) { 1 }; [b]", "if (a) { 1 };\n[b];\n"),
+            (
+                "fn() { if (a) { 1 }; -b }",
+                "fn() {\n    if (a) { 1 };\n    -b\n};\n",
+            ),
+        ];
+        for (input, expected) in test_cases {
+            let formatted = format(input);
+            assert_eq!(formatted, expected, "input: {input}");
+            assert_stable(input, &formatted);
+        }
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let input = "let result = combine(firstArgumentValue, secondArgumentValue, [1, 2, 3], thirdArgumentValue);";
+        let expected = "\
+let result = combine(
+    firstArgumentValue,
+    secondArgumentValue,
+    [1, 2, 3],
+    thirdArgumentValue
+);
+";
+        assert_eq!(format(input), expected);
+        assert_stable(input, expected);
+
+        let input = "let xs = [aaaaaaaaaa, bbbbbbbbbb, [cccccccccc, dddddddddd, eeeeeeeeee], ffffffffff, g];";
+        let expected = "\
+let xs = [
+    aaaaaaaaaa,
+    bbbbbbbbbb,
+    [cccccccccc, dddddddddd, eeeeeeeeee],
+    ffffffffff,
+    g
+];
+";
+        assert_eq!(format(input), expected);
+        assert_stable(input, expected);
+
+        let input = "map(items, fn(item) { let doubled = item * 2; doubled + 1 })";
+        let expected = "\
+map(items, fn(item) {
+    let doubled = item * 2;
+    doubled + 1
+});
+";
+        assert_eq!(format(input), expected);
+        assert_stable(input, expected);
+    }
+
+    #[test]
+    fn test_if_chain_breaks_together() {
+        let input = "let sign = fn(n) { if (n < 0) { -1 } else if (n == 0) { 0 } else { let one = 1; one } };";
+        let expected = "\
+let sign = fn(n) {
+    if (n < 0) {
+        -1
+    } else if (n == 0) {
+        0
+    } else {
+        let one = 1;
+        one
+    }
+};
+";
+        assert_eq!(format(input), expected);
+        assert_stable(input, expected);
+    }
+
+    #[test]
+    fn test_every_width_preserves_program() {
+        let input = "\
+let apply = fn(f, xs) { if (len(xs) == 0) { [] } else { push(apply(f, rest(xs)), f(first(xs))) } };
+let config = {\"name\": \"monkey\", \"tags\": [\"a\", \"b\"], \"scale\": fn(x) { x * (2 + 3) }};
+puts(apply(config[\"scale\"], [1, 2, 3])[0] - -1, !(true == false));
+";
+        for width in 0..=100 {
+            let formatted = format_source(input, width).unwrap();
+            assert_same_program(input, &formatted);
+            assert_eq!(
+                format_source(&formatted, width).unwrap(),
+                formatted,
+                "width: {width}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_width() {
+        let input = "let point = {\"x\": 1, \"y\": 2};";
+        assert_eq!(
+            format_source(input, 20).unwrap(),
+            "let point = {\n    \"x\": 1,\n    \"y\": 2\n};\n"
+        );
+        assert_eq!(
+            format_source(input, 40).unwrap(),
+            "let point = {\"x\": 1, \"y\": 2};\n"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let test_cases = [
+            ("// only a comment", "// only a comment\n"),
+            (
+                "// header\n\nlet x = 1; // trailing\n// before y\nlet y = 2;",
+                "// header\n\nlet x = 1; // trailing\n// before y\nlet y = 2;\n",
+            ),
+            (
+                "let f = fn() {\n  // nothing yet\n};",
+                "let f = fn() {\n    // nothing yet\n};\n",
+            ),
+            (
+                "fn() { x // the value\n}",
+                "fn() {\n    x // the value\n};\n",
+            ),
+            (
+                "let x = add(1, /* two */ 2);",
+                "let x = add(1, /* two */ 2);\n",
+            ),
+            ("let x = 1 // c\n+ 2;", "let x = 1 + // c\n    2;\n"),
+            ("f(1, // one\n2)", "f(\n    1, // one\n    2\n);\n"),
+            (
+                "if (x) { 1 } // after if\nelse { 2 }",
+                "if (x) {\n    1\n} // after if\nelse {\n    2\n}\n",
+            ),
+            ("[1, /* mid */ 2]", "[1, /* mid */ 2];\n"),
+            ("f(1,\n// two\n2)", "f(\n    1,\n    // two\n    2\n);\n"),
+            ("[1, 2 // end\n]", "[\n    1,\n    2 // end\n];\n"),
+            ("[/* none */]", "[/* none */];\n"),
+            ("{\"a\": /* one */ 1}", "{\"a\": /* one */ 1};\n"),
+            ("fn(a, /* b */ b) { a }", "fn(a, /* b */ b) { a };\n"),
+            ("let x =\n// c\n1;", "let x =\n    // c\n    1;\n"),
+            (
+                "if (x) { 1 }\n// a\n/* b */ else { 2 }",
+                "if (x) {\n    1\n}\n// a\n/* b */ else {\n    2\n}\n",
+            ),
+            (
+                "/* a\n   b */\nlet x = 1;\n// end",
+                "/* a\n   b */\nlet x = 1;\n// end\n",
+            ),
+            (
+                "if (a) {\n  1\n  // after\n}",
+                "if (a) {\n    1\n    // after\n}\n",
+            ),
+        ];
+        for (input, expected) in test_cases {
+            let formatted = format(input);
+            assert_eq!(formatted, expected, "input: {input}");
+            assert_stable(input, &formatted);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let errors = format_source("let = 1;", DEFAULT_WIDTH).unwrap_err();
+        assert_eq!(errors.len(), 1);
+    }
+}
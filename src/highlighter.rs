@@ -0,0 +1,357 @@
+use nu_ansi_term::{Color, Style};
+use reedline::{Highlighter, StyledText};
+
+use crate::lexer::Lexer;
+use crate::token::{Token, TokenType};
+
+/// The environment variable that overrides the style of token classes, as
+/// read by `Theme::with_overrides`.
+pub const THEME_VAR: &str = "MONKEY_THEME";
+
+/// The style of each kind of token in the REPL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub keyword: Style,
+    pub identifier: Style,
+    pub integer: Style,
+    pub string: Style,
+    pub operator: Style,
+    pub delimiter: Style,
+    pub comment: Style,
+    pub illegal: Style,
+    /// Whitespace between tokens.
+    pub plain: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            keyword: Style::new().bold().fg(Color::Magenta),
+            identifier: Style::new(),
+            integer: Style::new().fg(Color::Cyan),
+            string: Style::new().fg(Color::Green),
+            operator: Style::new().fg(Color::Yellow),
+            delimiter: Style::new().fg(Color::Blue),
+            comment: Style::new().italic().fg(Color::DarkGray),
+            illegal: Style::new().bold().fg(Color::White).on(Color::Red),
+            plain: Style::new(),
+        }
+    }
+}
+
+impl Theme {
+    /// A theme without any styling, for terminals that should not be
+    /// colored.
+    pub fn plain() -> Theme {
+        let plain = Style::new();
+        Theme {
+            keyword: plain,
+            identifier: plain,
+            integer: plain,
+            string: plain,
+            operator: plain,
+            delimiter: plain,
+            comment: plain,
+            illegal: plain,
+            plain,
+        }
+    }
+
+    /// The default theme with the overrides of `MONKEY_THEME` applied, or
+    /// `plain` when the `NO_COLOR` convention asks for no color.
+    pub fn from_env() -> Result<Theme, String> {
+        if let Some(value) = std::env::var_os("NO_COLOR")
+            && !value.is_empty()
+        {
+            return Ok(Theme::plain());
+        }
+        match std::env::var(THEME_VAR) {
+            Ok(spec) => Theme::default().with_overrides(&spec),
+            Err(_) => Ok(Theme::default()),
+        }
+    }
+
+    /// Applies `spec`, a comma-separated list of `class=style` entries such
+    /// as `keyword=bold red,illegal=white on red`. The classes are the names
+    /// of the fields. A style is any of `bold`, `dimmed`, `italic` and
+    /// `underline`, a color, and `on` followed by a background color; an
+    /// empty one leaves the class unstyled. Colors are names like `cyan` or
+    /// `light_blue`, numbers of the 256-color palette, or `#rrggbb`.
+    pub fn with_overrides(mut self, spec: &str) -> Result<Theme, String> {
+        for entry in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let Some((class, style)) = entry.split_once('=') else {
+                return Err(format!("expected `class=style`, found `{entry}`"));
+            };
+            *self.class_mut(class.trim())? = parse_style(style)?;
+        }
+        Ok(self)
+    }
+
+    fn class_mut(&mut self, class: &str) -> Result<&mut Style, String> {
+        Ok(match class {
+            "keyword" => &mut self.keyword,
+            "identifier" => &mut self.identifier,
+            "integer" => &mut self.integer,
+            "string" => &mut self.string,
+            "operator" => &mut self.operator,
+            "delimiter" => &mut self.delimiter,
+            "comment" => &mut self.comment,
+            "illegal" => &mut self.illegal,
+            "plain" => &mut self.plain,
+            _ => return Err(format!("unknown token class `{class}`")),
+        })
+    }
+
+    fn style(&self, token_type: TokenType) -> Style {
+        match token_type {
+            TokenType::Illegal => self.illegal,
+            TokenType::Eof => self.plain,
+            TokenType::Comment => self.comment,
+            TokenType::Identifier => self.identifier,
+            TokenType::Int => self.integer,
+            TokenType::String => self.string,
+            TokenType::Assign
+            | TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Bang
+            | TokenType::Asterisk
+            | TokenType::Slash
+            | TokenType::PlusEqual
+            | TokenType::MinusEqual
+            | TokenType::AsteriskEqual
+            | TokenType::SlashEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::BangEqual
+            | TokenType::EqualEqual => self.operator,
+            TokenType::Comma
+            | TokenType::Semicolon
+            | TokenType::Colon
+            | TokenType::LeftParen
+            | TokenType::RightParen
+            | TokenType::LeftBrace
+            | TokenType::RightBrace
+            | TokenType::LeftBracket
+            | TokenType::RightBracket => self.delimiter,
+            TokenType::Function
+            | TokenType::Let
+            | TokenType::True
+            | TokenType::False
+            | TokenType::If
+            | TokenType::Else
+            | TokenType::Return => self.keyword,
+        }
+    }
+}
+
+fn parse_style(text: &str) -> Result<Style, String> {
+    let mut style = Style::new();
+    let mut words = text.split_whitespace();
+    while let Some(word) = words.next() {
+        style = match word {
+            "bold" => style.bold(),
+            "dimmed" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            "on" => {
+                let color = words.next().ok_or("`on` needs a color")?;
+                style.on(parse_color(color)?)
+            }
+            color => style.fg(parse_color(color)?),
+        };
+    }
+    Ok(style)
+}
+
+fn parse_color(name: &str) -> Result<Color, String> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |start: usize| u8::from_str_radix(hex.get(start..start + 2)?, 16).ok();
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(red), Some(green), Some(blue)) => Ok(Color::Rgb(red, green, blue)),
+            _ => Err(format!("invalid color `{name}`")),
+        };
+    }
+    if let Ok(index) = name.parse() {
+        return Ok(Color::Fixed(index));
+    }
+    Ok(match name {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "dark_gray" => Color::DarkGray,
+        "light_red" => Color::LightRed,
+        "light_green" => Color::LightGreen,
+        "light_yellow" => Color::LightYellow,
+        "light_blue" => Color::LightBlue,
+        "light_magenta" => Color::LightMagenta,
+        "light_cyan" => Color::LightCyan,
+        "light_gray" => Color::LightGray,
+        _ => return Err(format!("unknown color `{name}`")),
+    })
+}
+
+/// Colors the REPL buffer by running the lexer over it, so highlighting
+/// always agrees with how the input will be tokenized.
+pub struct MonkeyHighlighter {
+    theme: Theme,
+}
+
+impl MonkeyHighlighter {
+    pub fn new(theme: Theme) -> MonkeyHighlighter {
+        MonkeyHighlighter { theme }
+    }
+
+    fn push(&self, styled: &mut StyledText, line: &str, position: &mut usize, token: &Token) {
+        let span = token.span;
+        if span.start > *position {
+            styled.push((self.theme.plain, line[*position..span.start].to_string()));
+        }
+        if span.end > span.start {
+            let style = self.theme.style(token.token_type);
+            styled.push((style, line[span.start..span.end].to_string()));
+        }
+        *position = span.end.max(*position);
+    }
+}
+
+impl Highlighter for MonkeyHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let mut styled = StyledText::new();
+        let mut lexer = Lexer::new(line).with_trivia();
+        let mut position = 0;
+        loop {
+            let token = lexer.next_token();
+            for comment in &token.leading_trivia {
+                self.push(&mut styled, line, &mut position, comment);
+            }
+            if token.is_type(TokenType::Eof) {
+                break;
+            }
+            self.push(&mut styled, line, &mut position, &token);
+        }
+        if position < line.len() {
+            styled.push((self.theme.plain, line[position..].to_string()));
+        }
+        styled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn highlight_with(theme: Theme, line: &str) -> Vec<(Style, String)> {
+        MonkeyHighlighter::new(theme).highlight(line, 0).buffer
+    }
+
+    fn highlight(line: &str) -> Vec<(Style, String)> {
+        highlight_with(Theme::default(), line)
+    }
+
+    #[test]
+    fn test_highlight() {
+        let theme = Theme::default();
+        let expected = vec![
+            (theme.keyword, "let".to_string()),
+            (theme.plain, " ".to_string()),
+            (theme.identifier, "x".to_string()),
+            (theme.plain, " ".to_string()),
+            (theme.operator, "=".to_string()),
+            (theme.plain, " ".to_string()),
+            (theme.delimiter, "[".to_string()),
+            (theme.integer, "5".to_string()),
+            (theme.delimiter, ",".to_string()),
+            (theme.plain, " ".to_string()),
+            (theme.string, "\"five\"".to_string()),
+            (theme.delimiter, "]".to_string()),
+            (theme.delimiter, ";".to_string()),
+            (theme.plain, " ".to_string()),
+            (theme.comment, "// done".to_string()),
+        ];
+        assert_eq!(highlight("let x = [5, \"five\"]; // done"), expected);
+    }
+
+    #[test]
+    fn test_highlight_keywords_and_illegal() {
+        let theme = Theme::default();
+        let styled = highlight("return returned @");
+        assert_eq!(styled[0], (theme.keyword, "return".to_string()));
+        assert_eq!(styled[2], (theme.identifier, "returned".to_string()));
+        assert_eq!(styled[4], (theme.illegal, "@".to_string()));
+    }
+
+    #[test]
+    fn test_highlight_preserves_text() {
+        let test_cases = [
+            "",
+            "   ",
+            "fn(a, b) {\n\ta + b\n}",
+            "\"unterminated",
+            "/* open",
+            "\"bad \\q escape\"",
+            "let é = \"ünï\"; # @",
+            "x += 1 /* a /* b */ c */ // end\n",
+        ];
+        for input in test_cases {
+            let text: String = highlight(input).into_iter().map(|(_, text)| text).collect();
+            assert_eq!(text, input);
+        }
+    }
+
+    #[test]
+    fn test_theme_overrides() {
+        let theme = Theme::default()
+            .with_overrides("keyword=bold red, integer = #ff8800,illegal=white on 196,identifier=")
+            .unwrap();
+        let expected = Theme {
+            keyword: Style::new().bold().fg(Color::Red),
+            integer: Style::new().fg(Color::Rgb(0xff, 0x88, 0x00)),
+            illegal: Style::new().fg(Color::White).on(Color::Fixed(196)),
+            identifier: Style::new(),
+            ..Theme::default()
+        };
+        assert_eq!(theme, expected);
+        let styled = highlight_with(theme, "let x @");
+        assert_eq!(styled[0], (expected.keyword, "let".to_string()));
+        assert_eq!(styled[4], (expected.illegal, "@".to_string()));
+    }
+
+    #[test]
+    fn test_theme_override_errors() {
+        let test_cases = [
+            ("keyword", "expected `class=style`, found `keyword`"),
+            ("number=red", "unknown token class `number`"),
+            ("keyword=mauve", "unknown color `mauve`"),
+            ("keyword=#12345", "invalid color `#12345`"),
+            ("keyword=bold on", "`on` needs a color"),
+        ];
+        for (spec, expected) in test_cases {
+            assert_eq!(
+                Theme::default().with_overrides(spec),
+                Err(expected.to_string()),
+                "spec: {spec}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_plain_theme() {
+        let plain = Style::new();
+        assert!(
+            highlight_with(Theme::plain(), "let x = 1;")
+                .iter()
+                .all(|(style, _)| *style == plain)
+        );
+    }
+}
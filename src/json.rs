@@ -0,0 +1,118 @@
+use std::fmt::{self, Write};
+
+/// A minimal JSON document, enough for the CLI's machine-readable output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Json {
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    /// Fields keep their insertion order.
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub fn string(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        let padding = "  ".repeat(indent + 1);
+        match self {
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write!(f, "{}", escape(value)),
+            Json::Array(elements) if elements.is_empty() => write!(f, "[]"),
+            Json::Array(elements) => {
+                writeln!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    write!(f, "{padding}")?;
+                    element.write_indented(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < elements.len() { "," } else { "" })?;
+                }
+                write!(f, "{}]", "  ".repeat(indent))
+            }
+            Json::Object(fields) if fields.is_empty() => write!(f, "{{}}"),
+            Json::Object(fields) => {
+                writeln!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    write!(f, "{padding}{}: ", escape(key))?;
+                    value.write_indented(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < fields.len() { "," } else { "" })?;
+                }
+                write!(f, "{}}}", "  ".repeat(indent))
+            }
+        }
+    }
+}
+
+/// Pretty-prints with two-space indentation.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+/// Quotes `value` as a JSON string literal.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if u32::from(ch) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", u32::from(ch));
+            }
+            ch => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        let test_cases = [
+            ("plain", r#""plain""#),
+            ("say \"hi\"", r#""say \"hi\"""#),
+            ("a\\b", r#""a\\b""#),
+            ("line\nbreak\t", r#""line\nbreak\t""#),
+            ("\u{1}", r#""\u0001""#),
+            ("héllo", r#""héllo""#),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(escape(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let json = Json::Object(vec![
+            ("name", Json::string("x")),
+            (
+                "values",
+                Json::Array(vec![Json::Number(-1), Json::Bool(true)]),
+            ),
+            ("empty", Json::Array(vec![])),
+            ("nothing", Json::Object(vec![])),
+        ]);
+        let expected = r#"{
+  "name": "x",
+  "values": [
+    -1,
+    true
+  ],
+  "empty": [],
+  "nothing": {}
+}"#;
+        assert_eq!(json.to_string(), expected);
+    }
+}
@@ -1,76 +1,264 @@
-use crate::token::{Token, TokenType};
+use std::fmt;
 
+use crate::token::{self, Span, Token, TokenType};
+
+/// A problem found while scanning. The lexer still produces a best-effort
+/// token so parsing can continue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexError {
+    UnterminatedString { start: Span },
+    UnterminatedComment { start: Span },
+    InvalidEscape { sequence: String, span: Span },
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnterminatedString { start: span }
+            | LexError::UnterminatedComment { start: span }
+            | LexError::InvalidEscape { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::UnterminatedString { .. } => write!(f, "unterminated string literal"),
+            LexError::UnterminatedComment { .. } => write!(f, "unterminated block comment"),
+            LexError::InvalidEscape { sequence, .. } => {
+                write!(f, "invalid escape sequence `{sequence}`")
+            }
+        }
+    }
+}
+
+/// Scans Monkey source into tokens. The cursor walks the input by byte
+/// offset, one UTF-8 character at a time, so lexing is linear in the input
+/// length and every span lands on a character boundary.
+///
+/// Comments are skipped unless `with_trivia` is used, in which case each one
+/// becomes a `Comment` token in the `leading_trivia` of the token after it.
 pub struct Lexer<'a> {
     input: &'a str,
     position: usize,
-    read_position: usize,
-    ch: char,
+    ch: Option<char>,
+    line: usize,
+    column: usize,
+    errors: Vec<LexError>,
+    keep_trivia: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Lexer<'a> {
-        let mut lexer = Lexer {
+        Lexer {
             input,
             position: 0,
-            read_position: 0,
-            ch: char::default(),
-        };
-        lexer.advance();
-        lexer
+            ch: input.chars().next(),
+            line: 1,
+            column: 1,
+            errors: vec![],
+            keep_trivia: false,
+        }
     }
 
-    fn peek(&self) -> char {
-        if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_position).unwrap()
+    #[must_use]
+    pub fn with_trivia(self) -> Lexer<'a> {
+        Lexer {
+            keep_trivia: true,
+            ..self
         }
     }
 
+    /// Returns the errors found since the last call.
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn current_span(&self) -> Span {
+        let end = self.position + self.ch.map_or(0, char::len_utf8);
+        Span::new(self.position, end, self.line, self.column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        let next = self.position + self.ch.map_or(0, char::len_utf8);
+        self.input[next..].chars().next()
+    }
+
     pub fn advance(&mut self) {
-        self.ch = if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input.chars().nth(self.read_position).unwrap()
+        let Some(ch) = self.ch else {
+            return;
         };
-        self.position = self.read_position;
-        self.read_position += 1;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        self.position += ch.len_utf8();
+        self.ch = self.input[self.position..].chars().next();
     }
 
-    fn read_number(&mut self) -> Token {
+    fn advance_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
         let start = self.position;
-        while self.ch.is_ascii_digit() {
+        while self.ch.is_some_and(&predicate) {
             self.advance();
         }
-        let literal = &self.input[start..self.position];
+        &self.input[start..self.position]
+    }
+
+    fn read_number(&mut self) -> Token {
+        let literal = self.advance_while(|ch| ch.is_ascii_digit());
         Token::new(TokenType::Int, literal)
     }
     fn read_identifier(&mut self) -> Token {
-        let start = self.position;
-        while self.ch.is_ascii_alphabetic() {
-            self.advance();
+        let literal = self.advance_while(|ch| ch.is_ascii_alphabetic());
+        let token_type = token::lookup_keyword(literal).unwrap_or(TokenType::Identifier);
+        Token::new(token_type, literal)
+    }
+
+    fn read_string(&mut self) -> Token {
+        let start = self.current_span();
+        let mut value = String::new();
+        self.advance();
+        loop {
+            match self.ch {
+                None => {
+                    self.errors.push(LexError::UnterminatedString { start });
+                    break;
+                }
+                Some('"') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') => self.read_escape(&mut value),
+                Some(ch) => {
+                    value.push(ch);
+                    self.advance();
+                }
+            }
         }
-        let literal = &self.input[start..self.position];
-        let token_type = match literal {
-            "fn" => TokenType::Function,
-            "let" => TokenType::Let,
-            "true" => TokenType::True,
-            "false" => TokenType::False,
-            "if" => TokenType::If,
-            "else" => TokenType::Else,
-            "return" => TokenType::Return,
-            _ => TokenType::Identifier,
+        Token::new(TokenType::String, &value)
+    }
+
+    fn read_escape(&mut self, value: &mut String) {
+        let escape_start = self.current_span();
+        self.advance();
+        let escaped = match self.ch {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('"') => Some('"'),
+            Some('\\') => Some('\\'),
+            _ => None,
         };
-        Token::new(token_type, literal)
+        if let Some(escaped) = escaped {
+            value.push(escaped);
+            self.advance();
+            return;
+        }
+        if self.ch == Some('u') && self.peek() == Some('{') {
+            self.advance();
+            self.advance();
+            let digits = self.advance_while(|ch| ch.is_ascii_hexdigit());
+            let escaped = u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32);
+            if let (Some(escaped), Some('}')) = (escaped, self.ch) {
+                value.push(escaped);
+                self.advance();
+                return;
+            }
+            if self.ch == Some('}') {
+                self.advance();
+            }
+        } else if self.ch.is_some() {
+            self.advance();
+        }
+        let sequence = &self.input[escape_start.start..self.position];
+        value.push_str(sequence);
+        self.errors.push(LexError::InvalidEscape {
+            sequence: sequence.to_string(),
+            span: Span::new(
+                escape_start.start,
+                self.position,
+                escape_start.line,
+                escape_start.column,
+            ),
+        });
     }
 
-    pub fn next_token(&mut self) -> Token {
-        while self.ch.is_whitespace() {
+    /// Reads a `//` comment up to the end of the line, or a `/* */` comment
+    /// including any comments nested inside it.
+    fn read_comment(&mut self) -> Token {
+        let start = self.current_span();
+        if self.peek() == Some('/') {
+            self.advance_while(|ch| ch != '\n');
+        } else {
+            self.advance();
             self.advance();
+            let mut depth = 1;
+            while depth > 0 {
+                match (self.ch, self.peek()) {
+                    (None, _) => {
+                        self.errors.push(LexError::UnterminatedComment {
+                            start: Span::new(
+                                start.start,
+                                start.start + 2,
+                                start.line,
+                                start.column,
+                            ),
+                        });
+                        break;
+                    }
+                    (Some('/'), Some('*')) => {
+                        depth += 1;
+                        self.advance();
+                    }
+                    (Some('*'), Some('/')) => {
+                        depth -= 1;
+                        self.advance();
+                    }
+                    _ => {}
+                }
+                self.advance();
+            }
         }
-        let token = match self.ch {
+        let literal = &self.input[start.start..self.position];
+        Token::new(TokenType::Comment, literal).with_span(Span::new(
+            start.start,
+            self.position,
+            start.line,
+            start.column,
+        ))
+    }
+
+    pub fn next_token(&mut self) -> Token {
+        let mut trivia = vec![];
+        loop {
+            self.advance_while(char::is_whitespace);
+            if self.ch != Some('/') || !matches!(self.peek(), Some('/' | '*')) {
+                break;
+            }
+            let comment = self.read_comment();
+            if self.keep_trivia {
+                trivia.push(comment);
+            }
+        }
+        let (start, line, column) = (self.position, self.line, self.column);
+        let mut token = self
+            .read_token()
+            .with_span(Span::new(start, self.position, line, column));
+        token.leading_trivia = trivia;
+        token
+    }
+
+    fn read_token(&mut self) -> Token {
+        let Some(ch) = self.ch else {
+            return Token::new(TokenType::Eof, "");
+        };
+        let token = match ch {
             '=' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::EqualEqual, "==")
                 } else {
@@ -78,7 +266,7 @@ impl<'a> Lexer<'a> {
                 }
             }
             '<' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::LessEqual, "<=")
                 } else {
@@ -86,7 +274,7 @@ impl<'a> Lexer<'a> {
                 }
             }
             '>' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::GreaterEqual, ">=")
                 } else {
@@ -94,7 +282,7 @@ impl<'a> Lexer<'a> {
                 }
             }
             '+' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::PlusEqual, "+=")
                 } else {
@@ -102,7 +290,7 @@ impl<'a> Lexer<'a> {
                 }
             }
             '-' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::MinusEqual, "-=")
                 } else {
@@ -110,15 +298,15 @@ impl<'a> Lexer<'a> {
                 }
             }
             '*' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::AsteriskEqual, "*=")
                 } else {
-                    Token::new(TokenType::Asterisk, "(")
+                    Token::new(TokenType::Asterisk, "*")
                 }
             }
             '/' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::SlashEqual, "/=")
                 } else {
@@ -126,7 +314,7 @@ impl<'a> Lexer<'a> {
                 }
             }
             '!' => {
-                if self.peek() == '=' {
+                if self.peek() == Some('=') {
                     self.advance();
                     Token::new(TokenType::BangEqual, "!=")
                 } else {
@@ -134,24 +322,18 @@ impl<'a> Lexer<'a> {
                 }
             }
             ';' => Token::new(TokenType::Semicolon, ";"),
+            ':' => Token::new(TokenType::Colon, ":"),
             '(' => Token::new(TokenType::LeftParen, "("),
             ')' => Token::new(TokenType::RightParen, ")"),
             ',' => Token::new(TokenType::Comma, ","),
             '{' => Token::new(TokenType::LeftBrace, "{"),
             '}' => Token::new(TokenType::RightBrace, "}"),
-            '\0' => Token::new(TokenType::Eof, ""),
-            _ => {
-                if self.ch.is_ascii_alphabetic() {
-                    return self.read_identifier();
-                }
-                if self.ch.is_ascii_digit() {
-                    return self.read_number();
-                }
-                return Token::new(
-                    TokenType::Illegal,
-                    std::str::from_utf8(&[self.ch as u8]).unwrap(),
-                );
-            }
+            '[' => Token::new(TokenType::LeftBracket, "["),
+            ']' => Token::new(TokenType::RightBracket, "]"),
+            '"' => return self.read_string(),
+            _ if ch.is_ascii_alphabetic() => return self.read_identifier(),
+            _ if ch.is_ascii_digit() => return self.read_number(),
+            _ => Token::new(TokenType::Illegal, ch.encode_utf8(&mut [0; 4])),
         };
         self.advance();
         token
@@ -162,6 +344,13 @@ impl<'a> Lexer<'a> {
 mod test {
     use super::*;
 
+    fn assert_token(actual: &Token, expected: &Token) {
+        assert_eq!(
+            (actual.token_type, actual.literal.as_str()),
+            (expected.token_type, expected.literal.as_str())
+        );
+    }
+
     #[test]
     fn test_punctuation() {
         let expected_tokens = [
@@ -179,7 +368,7 @@ mod test {
         let input = "=+(){},;";
         let mut lexer = Lexer::new(input);
         for expected_token in expected_tokens {
-            assert_eq!(lexer.next_token(), expected_token);
+            assert_token(&lexer.next_token(), &expected_token);
         }
     }
 
@@ -234,7 +423,7 @@ mod test {
         ";
         let mut lexer = Lexer::new(input);
         for expected_token in expected_tokens {
-            assert_eq!(lexer.next_token(), expected_token);
+            assert_token(&lexer.next_token(), &expected_token);
             println!("Passed {expected_token:?}");
         }
     }
@@ -263,7 +452,7 @@ mod test {
         ];
         let mut lexer = Lexer::new(input);
         for expected_token in expected_tokens {
-            assert_eq!(lexer.next_token(), expected_token);
+            assert_token(&lexer.next_token(), &expected_token);
             println!("Passed {expected_token:?}");
         }
     }
@@ -325,7 +514,7 @@ mod test {
         ];
         let mut lexer = Lexer::new(input);
         for expected_token in expected_tokens {
-            assert_eq!(lexer.next_token(), expected_token);
+            assert_token(&lexer.next_token(), &expected_token);
             println!("Passed {expected_token:?}");
         }
     }
@@ -372,8 +561,248 @@ mod test {
         ];
         let mut lexer = Lexer::new(input);
         for expected_token in expected_tokens {
-            assert_eq!(lexer.next_token(), expected_token);
+            assert_token(&lexer.next_token(), &expected_token);
             println!("Passed {expected_token:?}");
         }
     }
+
+    #[test]
+    fn test_spans() {
+        let input = "let x = 10;\n  x >= 5;";
+        let expected_spans = [
+            (TokenType::Let, Span::new(0, 3, 1, 1)),
+            (TokenType::Identifier, Span::new(4, 5, 1, 5)),
+            (TokenType::Assign, Span::new(6, 7, 1, 7)),
+            (TokenType::Int, Span::new(8, 10, 1, 9)),
+            (TokenType::Semicolon, Span::new(10, 11, 1, 11)),
+            (TokenType::Identifier, Span::new(14, 15, 2, 3)),
+            (TokenType::GreaterEqual, Span::new(16, 18, 2, 5)),
+            (TokenType::Int, Span::new(19, 20, 2, 8)),
+            (TokenType::Semicolon, Span::new(20, 21, 2, 9)),
+            (TokenType::Eof, Span::new(21, 21, 2, 10)),
+        ];
+        let mut lexer = Lexer::new(input);
+        for (token_type, span) in expected_spans {
+            let token = lexer.next_token();
+            assert_eq!((token.token_type, token.span), (token_type, span));
+        }
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        let input = "let é = 5 🙈 \0;\n日x";
+        let expected_tokens = [
+            (TokenType::Let, "let", Span::new(0, 3, 1, 1)),
+            (TokenType::Illegal, "é", Span::new(4, 6, 1, 5)),
+            (TokenType::Assign, "=", Span::new(7, 8, 1, 7)),
+            (TokenType::Int, "5", Span::new(9, 10, 1, 9)),
+            (TokenType::Illegal, "🙈", Span::new(11, 15, 1, 11)),
+            (TokenType::Illegal, "\0", Span::new(16, 17, 1, 13)),
+            (TokenType::Semicolon, ";", Span::new(17, 18, 1, 14)),
+            (TokenType::Illegal, "日", Span::new(19, 22, 2, 1)),
+            (TokenType::Identifier, "x", Span::new(22, 23, 2, 2)),
+            (TokenType::Eof, "", Span::new(23, 23, 2, 3)),
+        ];
+        let mut lexer = Lexer::new(input);
+        for (token_type, literal, span) in expected_tokens {
+            let token = lexer.next_token();
+            assert_eq!(
+                (token.token_type, token.literal.as_str(), token.span),
+                (token_type, literal, span)
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_input() {
+        let input = "a + 1; ".repeat(200_000);
+        let mut lexer = Lexer::new(&input);
+        let mut count = 0;
+        while !lexer.next_token().is_type(TokenType::Eof) {
+            count += 1;
+        }
+        assert_eq!(count, 800_000);
+    }
+
+    #[test]
+    fn test_strings() {
+        let input = r#""foobar" "foo bar" "" "a\nb\t\"c\"\\" "\u{1F648}\u{e9}" "日本""#;
+        let expected_tokens = [
+            Token::new(TokenType::String, "foobar"),
+            Token::new(TokenType::String, "foo bar"),
+            Token::new(TokenType::String, ""),
+            Token::new(TokenType::String, "a\nb\t\"c\"\\"),
+            Token::new(TokenType::String, "🙈é"),
+            Token::new(TokenType::String, "日本"),
+            Token::new(TokenType::Eof, ""),
+        ];
+        let mut lexer = Lexer::new(input);
+        for expected_token in expected_tokens {
+            assert_token(&lexer.next_token(), &expected_token);
+        }
+        let errors = lexer.take_errors();
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn test_string_span() {
+        let mut lexer = Lexer::new(r#"x = "a\"b";"#);
+        lexer.next_token();
+        lexer.next_token();
+        let token = lexer.next_token();
+        assert_eq!(token.span, Span::new(4, 10, 1, 5));
+        assert_token(&lexer.next_token(), &Token::new(TokenType::Semicolon, ";"));
+    }
+
+    #[test]
+    fn test_comments_are_skipped() {
+        let input = "
+        // a line comment
+        let x = 10 / 2; // trailing
+        /* a block
+           comment */ x /* inline */ / 5
+        /* outer /* nested */ still comment */ ;
+        //";
+        let expected_tokens = [
+            Token::new(TokenType::Let, "let"),
+            Token::new(TokenType::Identifier, "x"),
+            Token::new(TokenType::Assign, "="),
+            Token::new(TokenType::Int, "10"),
+            Token::new(TokenType::Slash, "/"),
+            Token::new(TokenType::Int, "2"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::Identifier, "x"),
+            Token::new(TokenType::Slash, "/"),
+            Token::new(TokenType::Int, "5"),
+            Token::new(TokenType::Semicolon, ";"),
+            Token::new(TokenType::Eof, ""),
+        ];
+        let mut lexer = Lexer::new(input);
+        for expected_token in expected_tokens {
+            let token = lexer.next_token();
+            assert_token(&token, &expected_token);
+            assert_eq!(token.leading_trivia, []);
+        }
+        assert_eq!(lexer.take_errors(), []);
+    }
+
+    #[test]
+    fn test_comments_as_trivia() {
+        let input = "// doc\nlet /* a /* b */ */ x = 1; // end";
+        let mut lexer = Lexer::new(input).with_trivia();
+        let let_token = lexer.next_token();
+        assert_token(&let_token, &Token::new(TokenType::Let, "let"));
+        assert_eq!(
+            let_token.leading_trivia,
+            [Token::new(TokenType::Comment, "// doc").with_span(Span::new(0, 6, 1, 1))]
+        );
+        let x = lexer.next_token();
+        assert_eq!(
+            x.leading_trivia,
+            [
+                Token::new(TokenType::Comment, "/* a /* b */ */")
+                    .with_span(Span::new(11, 26, 2, 5))
+            ]
+        );
+        for _ in 0..3 {
+            assert_eq!(lexer.next_token().leading_trivia, []);
+        }
+        let eof = lexer.next_token();
+        assert_token(&eof, &Token::new(TokenType::Eof, ""));
+        let trivia: Vec<&str> = eof
+            .leading_trivia
+            .iter()
+            .map(|comment| comment.literal.as_str())
+            .collect();
+        assert_eq!(trivia, ["// end"]);
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        let mut lexer = Lexer::new("1 /* a /* b */");
+        assert_token(&lexer.next_token(), &Token::new(TokenType::Int, "1"));
+        assert_token(&lexer.next_token(), &Token::new(TokenType::Eof, ""));
+        assert_eq!(
+            lexer.take_errors(),
+            [LexError::UnterminatedComment {
+                start: Span::new(2, 4, 1, 3)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let mut lexer = Lexer::new("let s = \n  \"abc;");
+        for _ in 0..3 {
+            lexer.next_token();
+        }
+        assert_token(&lexer.next_token(), &Token::new(TokenType::String, "abc;"));
+        assert_token(&lexer.next_token(), &Token::new(TokenType::Eof, ""));
+        assert_eq!(
+            lexer.take_errors(),
+            [LexError::UnterminatedString {
+                start: Span::new(11, 12, 2, 3)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        let test_cases = [
+            (r#""\q""#, "\\q"),
+            (r#""\u{110000}""#, "\\u{110000}"),
+            (r#""\u{}""#, "\\u{}"),
+            (r#""\u{41""#, "\\u{41"),
+        ];
+        for (input, sequence) in test_cases {
+            let mut lexer = Lexer::new(input);
+            let token = lexer.next_token();
+            assert_eq!(token.token_type, TokenType::String);
+            let errors = lexer.take_errors();
+            let Some(LexError::InvalidEscape {
+                sequence: actual,
+                span,
+            }) = errors.first()
+            else {
+                panic!("expected an invalid escape error for {input}, got {errors:?}")
+            };
+            assert_eq!(actual, sequence);
+            assert_eq!(span.start, 1);
+        }
+    }
+
+    #[test]
+    fn test_brackets() {
+        let expected_tokens = [
+            Token::new(TokenType::LeftBracket, "["),
+            Token::new(TokenType::Int, "1"),
+            Token::new(TokenType::Comma, ","),
+            Token::new(TokenType::Int, "2"),
+            Token::new(TokenType::RightBracket, "]"),
+            Token::new(TokenType::LeftBracket, "["),
+            Token::new(TokenType::Int, "0"),
+            Token::new(TokenType::RightBracket, "]"),
+            Token::new(TokenType::Eof, ""),
+        ];
+        let mut lexer = Lexer::new("[1, 2][0]");
+        for expected_token in expected_tokens {
+            assert_token(&lexer.next_token(), &expected_token);
+        }
+    }
+
+    #[test]
+    fn test_hash_tokens() {
+        let expected_tokens = [
+            Token::new(TokenType::LeftBrace, "{"),
+            Token::new(TokenType::String, "foo"),
+            Token::new(TokenType::Colon, ":"),
+            Token::new(TokenType::String, "bar"),
+            Token::new(TokenType::RightBrace, "}"),
+            Token::new(TokenType::Eof, ""),
+        ];
+        let mut lexer = Lexer::new(r#"{"foo": "bar"}"#);
+        for expected_token in expected_tokens {
+            assert_token(&lexer.next_token(), &expected_token);
+        }
+    }
 }
@@ -1,57 +1,57 @@
 mod ast;
+mod builtins;
+#[cfg(test)]
+mod canonical;
+mod cli;
+mod code;
+mod compiler;
+mod completer;
+mod diagnostic;
+mod dump;
+mod evaluator;
+mod formatter;
+mod highlighter;
+mod json;
 mod lexer;
+mod object;
 mod parser;
+mod repl;
+mod session;
+mod symbol_table;
 mod token;
-use crate::parser::Parser;
-use reedline::{
-    DefaultPrompt, DefaultPromptSegment, ExampleHighlighter, Reedline, Signal, Vi,
-    default_vi_insert_keybindings, default_vi_normal_keybindings,
-};
+mod vm;
+use std::panic;
+use std::process::ExitCode;
+use std::thread;
 
-fn repl() {
-    let commands = vec![
-        "let".into(),
-        "if".into(),
-        "else".into(),
-        "fn".into(),
-        "true".into(),
-        "false".into(),
-    ];
-    let mut line_editor = Reedline::create()
-        .with_edit_mode(Box::new(Vi::new(
-            default_vi_insert_keybindings(),
-            default_vi_normal_keybindings(),
-        )))
-        .with_highlighter(Box::new(ExampleHighlighter::new(commands)));
-    let prompt = DefaultPrompt::new(
-        DefaultPromptSegment::Basic("monkey".to_string()),
-        DefaultPromptSegment::Empty,
-    );
-    loop {
-        let sig = line_editor.read_line(&prompt);
-        match sig {
-            Ok(Signal::Success(buffer)) => {
-                let mut parser = Parser::new(&buffer);
-                if let Some(program) = parser.parse_program() {
-                    println!("{program}");
-                } else {
-                    println!("Invalid statement");
-                    for error in parser.errors {
-                        print!("{error}");
-                    }
-                }
-            }
-            Ok(Signal::CtrlD | Signal::CtrlC) => {
-                println!("Aborted");
-                break;
-            }
-            x => {
-                println!("{x:?}");
-            }
-        }
-    }
+use crate::cli::{Cli, Command};
+use crate::object::NATIVE_STACK_SIZE;
+
+/// Runs on a thread with a big enough stack for the evaluator to reach the
+/// call depth limit; see `object::MAX_CALL_DEPTH`.
+fn main() -> ExitCode {
+    thread::Builder::new()
+        .stack_size(NATIVE_STACK_SIZE)
+        .spawn(run)
+        .expect("failed to spawn the interpreter thread")
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
 }
 
-fn main() {
-    repl();
+fn run() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse_args(&args) {
+        Ok(Cli {
+            engine,
+            command: Command::Repl,
+        }) => {
+            repl::run(engine);
+            ExitCode::SUCCESS
+        }
+        Ok(Cli { engine, command }) => ExitCode::from(cli::run_command(engine, &command)),
+        Err(message) => {
+            eprintln!("error: {message}\n\n{}", cli::USAGE);
+            ExitCode::from(cli::EXIT_USAGE)
+        }
+    }
 }
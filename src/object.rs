@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast;
+use crate::code::Instructions;
+
+/// The most function calls either engine lets be in progress at once, the
+/// program itself counting as the first. Deeper recursion fails with a
+/// "stack overflow" error instead of crashing the interpreter.
+///
+/// The evaluator recurses through several Rust frames for every Monkey call,
+/// about 16 KiB of them in a debug build, so reaching this depth takes more
+/// native stack than a default thread has. That is why programs run on a
+/// thread of `NATIVE_STACK_SIZE`: raising this limit, or running the
+/// evaluator on a smaller stack, lets it crash before the limit is reached.
+pub const MAX_CALL_DEPTH: usize = 1024;
+
+/// The native stack of a thread that runs programs, enough for the evaluator
+/// to reach `MAX_CALL_DEPTH` with room to spare. Only the pages actually
+/// touched are committed.
+pub const NATIVE_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    Null,
+    ReturnValue(Box<Object>),
+    Error(String),
+    Function(Rc<Function>),
+    Builtin(Builtin),
+    CompiledFunction(Rc<CompiledFunction>),
+    Closure(Rc<Closure>),
+    Array(Vec<Object>),
+    Hash(HashMap<HashKey, HashPair>),
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) => "INTEGER",
+            Object::String(_) => "STRING",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::Null => "NULL",
+            Object::ReturnValue(_) => "RETURN_VALUE",
+            Object::Error(_) => "ERROR",
+            // Closures are what functions evaluate to in the VM, so they
+            // report the same type as the evaluator's functions.
+            Object::Function(_) | Object::Closure(_) => "FUNCTION",
+            Object::Builtin(_) => "BUILTIN",
+            Object::CompiledFunction(_) => "COMPILED_FUNCTION",
+            Object::Array(_) => "ARRAY",
+            Object::Hash(_) => "HASH",
+        }
+    }
+
+    /// Returns the key this object hashes to, or `None` if it cannot be used
+    /// as a hash key.
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::Integer(value) => Some(HashKey::Integer(*value)),
+            Object::Boolean(value) => Some(HashKey::Boolean(*value)),
+            Object::String(value) => Some(HashKey::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Object::Error(_))
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Null | Object::Boolean(false))
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::Integer(value) => write!(f, "{value}"),
+            Object::String(value) => write!(f, "{value}"),
+            Object::Boolean(value) => write!(f, "{value}"),
+            Object::Null => write!(f, "null"),
+            Object::ReturnValue(value) => write!(f, "{value}"),
+            Object::Error(message) => write!(f, "ERROR: {message}"),
+            Object::Function(function) => write!(f, "{function}"),
+            Object::Builtin(builtin) => write!(f, "builtin function {}", builtin.name),
+            Object::CompiledFunction(function) => write!(f, "{}", function.source),
+            Object::Closure(closure) => write!(f, "{}", closure.function.source),
+            Object::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(ToString::to_string).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Object::Hash(pairs) => {
+                let mut pairs: Vec<(&HashKey, &HashPair)> = pairs.iter().collect();
+                pairs.sort_by_key(|(key, _)| *key);
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(_, pair)| format!("{}: {}", pair.key, pair.value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+}
+
+/// A hash entry keeps the original key object so the hash can be displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub parameters: Vec<ast::Identifier>,
+    pub body: ast::BlockStatement,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+// Functions compare by identity; their environments may contain themselves.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter().map(ToString::to_string).collect();
+        write!(f, "fn({}) {}", parameters.join(", "), self.body)
+    }
+}
+
+/// A variable of a compiled function. Closures share it with the frame
+/// that defined it, so that, as with the evaluator's environments, a later
+/// `let` or assignment is seen by every function that captured it. It is
+/// `None` until its `let` has run.
+pub type Variable = Rc<RefCell<Option<Object>>>;
+
+/// Where a closure being created finds a variable it captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    /// A local of the enclosing function, by slot.
+    Local(usize),
+    /// A variable the enclosing function captured itself, by index.
+    Free(usize),
+    /// The enclosing function, through the name it was bound to.
+    Function,
+}
+
+/// A function lowered to bytecode by the compiler.
+#[derive(Debug)]
+pub struct CompiledFunction {
+    pub instructions: Instructions,
+    pub num_parameters: usize,
+    /// The names of the locals, indexed by slot.
+    pub local_names: Vec<String>,
+    /// The names of the captured variables, indexed like `captures`.
+    pub free_names: Vec<String>,
+    pub captures: Vec<Capture>,
+    /// The function literal this was compiled from, used for display.
+    pub source: String,
+}
+
+impl PartialEq for CompiledFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A compiled function together with the variables it captured.
+#[derive(Debug)]
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub free: Vec<Variable>,
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+pub type BuiltinFunction = fn(&[Object]) -> Object;
+
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub function: BuiltinFunction,
+}
+
+impl Builtin {
+    pub const fn new(name: &'static str, function: BuiltinFunction) -> Builtin {
+        Builtin { name, function }
+    }
+}
+
+// Builtin names are unique, so comparing them avoids comparing fn pointers.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Builtin({})", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Rc<RefCell<Environment>>>,
+    /// The number of function calls in progress while code runs in this
+    /// environment, counted as for `MAX_CALL_DEPTH`.
+    depth: usize,
+}
+
+impl Environment {
+    pub fn new() -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment {
+            store: HashMap::new(),
+            outer: None,
+            depth: 1,
+        }))
+    }
+
+    /// The environment of a call made `depth` calls deep, enclosed by the
+    /// environment the function was defined in.
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>, depth: usize) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment {
+            store: HashMap::new(),
+            outer: Some(outer),
+            depth,
+        }))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .outer
+                .as_ref()
+                .and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    /// The bindings of this scope alone, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, Object)> {
+        let mut bindings: Vec<_> = self
+            .store
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        bindings
+    }
+
+    /// A copy of this scope's bindings, to put back with `restore`.
+    pub fn snapshot(&self) -> HashMap<String, Object> {
+        self.store.clone()
+    }
+
+    pub fn restore(&mut self, store: HashMap<String, Object>) {
+        self.store = store;
+    }
+
+    pub fn set(&mut self, name: &str, value: Object) {
+        self.store.insert(name.to_string(), value);
+    }
+
+    /// Updates the nearest enclosing binding of `name`, returning false if
+    /// there is none.
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        if let Some(slot) = self.store.get_mut(name) {
+            *slot = value;
+            return true;
+        }
+        self.outer
+            .as_ref()
+            .is_some_and(|outer| outer.borrow_mut().assign(name, value))
+    }
+}
@@ -1,10 +1,16 @@
 use std::collections::HashMap;
+use std::fmt;
 
-use crate::ast;
+use crate::ast::{self, Node};
+use crate::diagnostic::Diagnostic;
 use crate::lexer;
-use crate::token::{Token, TokenType};
+use crate::token::{Span, Token, TokenType};
 
-#[derive(Copy, Clone)]
+/// Deeper expression nesting is reported as an error instead of overflowing
+/// the stack.
+const MAX_NESTING_DEPTH: usize = 256;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum Precedence {
     Lowest = 1,
     Equals,
@@ -13,17 +19,21 @@ enum Precedence {
     Product,
     Prefix,
     Call,
+    Index,
 }
 
 #[derive(Copy, Clone)]
 enum PrefixParser {
     Identifier,
     Integer,
+    String,
     Boolean,
     Prefix,
     Grouped,
     If,
     Function,
+    Array,
+    Hash,
 }
 
 #[derive(Copy, Clone)]
@@ -35,15 +45,171 @@ enum InfixParser {
     Equal,
     NotEqual,
     LessThan,
+    LessThanOrEqual,
     GreaterThan,
+    GreaterThanOrEqual,
     Call,
+    Index,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedToken { expected: TokenType, found: Token },
+    MissingPrefixParseFn { found: Token },
+    IntegerOverflow { token: Token },
+    UnterminatedBlock { open: Token },
+    IllegalCharacter { token: Token },
+    NestingTooDeep { token: Token },
+    Lex(lexer::LexError),
+}
+
+impl ParseError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E0001",
+            ParseError::MissingPrefixParseFn { .. } => "E0002",
+            ParseError::IntegerOverflow { .. } => "E0003",
+            ParseError::UnterminatedBlock { .. } => "E0004",
+            ParseError::IllegalCharacter { .. } => "E0005",
+            ParseError::NestingTooDeep { .. } => "E0006",
+            ParseError::Lex(lexer::LexError::UnterminatedString { .. }) => "E0007",
+            ParseError::Lex(lexer::LexError::InvalidEscape { .. }) => "E0008",
+            ParseError::Lex(lexer::LexError::UnterminatedComment { .. }) => "E0009",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { found: token, .. }
+            | ParseError::MissingPrefixParseFn { found: token }
+            | ParseError::IntegerOverflow { token }
+            | ParseError::UnterminatedBlock { open: token }
+            | ParseError::IllegalCharacter { token }
+            | ParseError::NestingTooDeep { token } => token.span,
+            ParseError::Lex(error) => error.span(),
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::new(self.code(), self.to_string(), self.span(), self.label());
+        match self {
+            ParseError::UnexpectedToken { expected, found } => match expected {
+                TokenType::RightParen | TokenType::RightBrace => {
+                    diagnostic.with_help(format!("insert {expected} before {}", found.describe()))
+                }
+                TokenType::Assign => diagnostic
+                    .with_help("`let` bindings take the form `let <name> = <value>;`".to_string()),
+                TokenType::LeftParen if found.is_type(TokenType::Identifier) => diagnostic
+                    .with_help(
+                        "wrap the condition in parentheses: `if (condition) { ... }`".to_string(),
+                    ),
+                TokenType::Identifier if is_keyword(found.token_type) => {
+                    diagnostic.with_note(format!(
+                        "`{}` is a reserved keyword and cannot be used as a name",
+                        found.literal
+                    ))
+                }
+                _ => diagnostic,
+            },
+            ParseError::MissingPrefixParseFn { found } => {
+                diagnostic.with_note(format!("{} cannot start an expression", found.token_type))
+            }
+            ParseError::IntegerOverflow { .. } => diagnostic.with_note(format!(
+                "integers are 64-bit signed and must be at most {}",
+                i64::MAX
+            )),
+            ParseError::UnterminatedBlock { .. } => {
+                diagnostic.with_help("add `}` at the end of the block".to_string())
+            }
+            ParseError::IllegalCharacter { .. } => diagnostic,
+            ParseError::NestingTooDeep { .. } => diagnostic.with_note(format!(
+                "expressions may be nested at most {MAX_NESTING_DEPTH} levels deep"
+            )),
+            ParseError::Lex(lexer::LexError::UnterminatedString { .. }) => {
+                diagnostic.with_help("add a closing `\"`".to_string())
+            }
+            ParseError::Lex(lexer::LexError::UnterminatedComment { .. }) => diagnostic
+                .with_note("block comments nest, so every `/*` needs its own `*/`".to_string())
+                .with_help("add a closing `*/`".to_string()),
+            ParseError::Lex(lexer::LexError::InvalidEscape { .. }) => diagnostic.with_note(
+                "valid escapes are `\\n`, `\\t`, `\\\"`, `\\\\` and `\\u{...}`".to_string(),
+            ),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            ParseError::UnexpectedToken { expected, .. } => format!("expected {expected}"),
+            ParseError::MissingPrefixParseFn { .. } => "expected an expression".to_string(),
+            ParseError::IntegerOverflow { .. } => "does not fit in 64 bits".to_string(),
+            ParseError::UnterminatedBlock { .. } => "this `{` is never closed".to_string(),
+            ParseError::IllegalCharacter { .. } => "not valid in Monkey source".to_string(),
+            ParseError::NestingTooDeep { .. } => "nested too deeply".to_string(),
+            ParseError::Lex(lexer::LexError::UnterminatedString { .. }) => {
+                "string starts here".to_string()
+            }
+            ParseError::Lex(lexer::LexError::InvalidEscape { .. }) => "unknown escape".to_string(),
+            ParseError::Lex(lexer::LexError::UnterminatedComment { .. }) => {
+                "comment starts here".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken { expected, found } => {
+                write!(f, "expected {expected}, found {}", found.describe())
+            }
+            ParseError::MissingPrefixParseFn { found } => {
+                write!(f, "expected expression, found {}", found.describe())
+            }
+            ParseError::IntegerOverflow { token } => {
+                write!(f, "integer literal `{}` is too large", token.literal)
+            }
+            ParseError::UnterminatedBlock { .. } => write!(f, "unterminated block"),
+            ParseError::IllegalCharacter { token } => {
+                write!(f, "unexpected character `{}`", token.literal)
+            }
+            ParseError::NestingTooDeep { .. } => write!(f, "expression is nested too deeply"),
+            ParseError::Lex(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn is_assignment(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Assign
+            | TokenType::PlusEqual
+            | TokenType::MinusEqual
+            | TokenType::AsteriskEqual
+            | TokenType::SlashEqual
+    )
+}
+
+fn is_keyword(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Function
+            | TokenType::Let
+            | TokenType::True
+            | TokenType::False
+            | TokenType::If
+            | TokenType::Else
+            | TokenType::Return
+    )
 }
 
 pub struct Parser<'a> {
     lexer: lexer::Lexer<'a>,
     current_token: Token,
     peek_token: Token,
-    pub errors: Vec<String>,
+    pub errors: Vec<ParseError>,
+    depth: usize,
     prefix_parse_fns: HashMap<TokenType, PrefixParser>,
     infix_parse_fns: HashMap<TokenType, InfixParser>,
     precedences: HashMap<TokenType, Precedence>,
@@ -57,13 +223,16 @@ impl<'a> Parser<'a> {
         let prefix_parse_fns = HashMap::from([
             (TokenType::Identifier, PrefixParser::Identifier),
             (TokenType::Int, PrefixParser::Integer),
+            (TokenType::String, PrefixParser::String),
             (TokenType::True, PrefixParser::Boolean),
             (TokenType::False, PrefixParser::Boolean),
             (TokenType::Bang, PrefixParser::Prefix),
             (TokenType::Minus, PrefixParser::Prefix),
-            (TokenType::Minus, PrefixParser::Prefix),
-            (TokenType::Minus, PrefixParser::Prefix),
-            (TokenType::Minus, PrefixParser::Prefix),
+            (TokenType::LeftParen, PrefixParser::Grouped),
+            (TokenType::If, PrefixParser::If),
+            (TokenType::Function, PrefixParser::Function),
+            (TokenType::LeftBracket, PrefixParser::Array),
+            (TokenType::LeftBrace, PrefixParser::Hash),
         ]);
         let infix_parse_fns = HashMap::from([
             (TokenType::Plus, InfixParser::Plus),
@@ -73,71 +242,301 @@ impl<'a> Parser<'a> {
             (TokenType::EqualEqual, InfixParser::Equal),
             (TokenType::BangEqual, InfixParser::NotEqual),
             (TokenType::Less, InfixParser::LessThan),
+            (TokenType::LessEqual, InfixParser::LessThanOrEqual),
             (TokenType::Greater, InfixParser::GreaterThan),
+            (TokenType::GreaterEqual, InfixParser::GreaterThanOrEqual),
             (TokenType::LeftParen, InfixParser::Call),
+            (TokenType::LeftBracket, InfixParser::Index),
         ]);
 
         let precedences = HashMap::from([
             (TokenType::EqualEqual, Precedence::Equals),
             (TokenType::BangEqual, Precedence::Equals),
             (TokenType::Less, Precedence::LessGreater),
+            (TokenType::LessEqual, Precedence::LessGreater),
             (TokenType::Greater, Precedence::LessGreater),
+            (TokenType::GreaterEqual, Precedence::LessGreater),
             (TokenType::Plus, Precedence::Sum),
             (TokenType::Minus, Precedence::Sum),
             (TokenType::Slash, Precedence::Product),
             (TokenType::Asterisk, Precedence::Product),
             (TokenType::LeftParen, Precedence::Call),
+            (TokenType::LeftBracket, Precedence::Index),
         ]);
-        Parser {
+        let mut parser = Parser {
             lexer,
             current_token,
             peek_token,
             errors: vec![],
+            depth: 0,
             prefix_parse_fns,
             infix_parse_fns,
             precedences,
-        }
+        };
+        parser.collect_lex_errors();
+        parser
     }
 
-    fn parse_prefix(&mut self, parser_type: PrefixParser) -> ast::Expression {
+    fn parse_prefix(&mut self, parser_type: PrefixParser) -> Option<ast::Expression> {
         match parser_type {
-            PrefixParser::Identifier => self.parse_identifier(),
+            PrefixParser::Identifier => Some(self.parse_identifier()),
             PrefixParser::Integer => self.parse_integer_literal(),
-            PrefixParser::Boolean => self.parse_boolean(),
+            PrefixParser::String => Some(self.parse_string_literal()),
+            PrefixParser::Boolean => Some(self.parse_boolean()),
             PrefixParser::Prefix => self.parse_prefix_expression(),
             PrefixParser::Grouped => self.parse_grouped_expression(),
             PrefixParser::If => self.parse_if_expression(),
             PrefixParser::Function => self.parse_function(),
+            PrefixParser::Array => self.parse_array_literal(),
+            PrefixParser::Hash => self.parse_hash_literal(),
+        }
+    }
+
+    fn parse_infix(
+        &mut self,
+        parser_type: InfixParser,
+        left: ast::Expression,
+    ) -> Option<ast::Expression> {
+        match parser_type {
+            InfixParser::Plus
+            | InfixParser::Minus
+            | InfixParser::Multiply
+            | InfixParser::Divide
+            | InfixParser::Equal
+            | InfixParser::NotEqual
+            | InfixParser::LessThan
+            | InfixParser::LessThanOrEqual
+            | InfixParser::GreaterThan
+            | InfixParser::GreaterThanOrEqual => self.parse_infix_expression(left),
+            InfixParser::Call => self.parse_call_expression(left),
+            InfixParser::Index => self.parse_index_expression(left),
         }
     }
 
     fn parse_identifier(&mut self) -> ast::Expression {
-        ast::Expression::Identifier(ast::Identifier::new(&self.current_token.literal))
+        ast::Expression::Identifier(ast::Identifier::new(&self.current_token))
     }
 
-    fn parse_integer_literal(&mut self) -> ast::Expression {
-        ast::Expression::IntegerLiteral(ast::IntegerLiteral::new(&self.current_token))
+    fn parse_integer_literal(&mut self) -> Option<ast::Expression> {
+        let Some(integer) = ast::IntegerLiteral::new(&self.current_token) else {
+            self.errors.push(ParseError::IntegerOverflow {
+                token: self.current_token.clone(),
+            });
+            return None;
+        };
+        Some(ast::Expression::IntegerLiteral(integer))
+    }
+    fn parse_string_literal(&mut self) -> ast::Expression {
+        ast::Expression::StringLiteral(ast::StringLiteral::new(&self.current_token))
     }
     fn parse_boolean(&mut self) -> ast::Expression {
         ast::Expression::Boolean(ast::Boolean::new(&self.current_token))
     }
-    fn parse_prefix_expression(&mut self) -> ast::Expression {
+    fn parse_prefix_expression(&mut self) -> Option<ast::Expression> {
         let prefix_token = self.current_token.clone();
         self.advance();
-        let expression = self.parse_expression().unwrap();
-        ast::Expression::PrefixExpression(ast::PrefixExpression::new(
-            prefix_token,
-            Box::new(expression),
+        let expression = self.parse_expression(Precedence::Prefix)?;
+        Some(ast::Expression::PrefixExpression(
+            ast::PrefixExpression::new(prefix_token, Box::new(expression)),
+        ))
+    }
+    fn parse_infix_expression(&mut self, left: ast::Expression) -> Option<ast::Expression> {
+        let operator_token = self.current_token.clone();
+        let precedence = self.current_precedence();
+        self.advance();
+        let right = self.parse_expression(precedence)?;
+        Some(ast::Expression::InfixExpression(ast::InfixExpression::new(
+            operator_token,
+            Box::new(left),
+            Box::new(right),
+        )))
+    }
+    fn parse_grouped_expression(&mut self) -> Option<ast::Expression> {
+        self.advance();
+        let expression = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RightParen) {
+            return None;
+        }
+        Some(expression)
+    }
+    fn parse_if_expression(&mut self) -> Option<ast::Expression> {
+        let if_token = self.current_token.clone();
+        if !self.expect_peek(TokenType::LeftParen) {
+            return None;
+        }
+        self.advance();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RightParen) || !self.expect_peek(TokenType::LeftBrace) {
+            return None;
+        }
+        let consequence = self.parse_block_statement()?;
+        let alternative = if self.peek_token.is_type(TokenType::Else) {
+            self.advance();
+            Some(self.parse_else_branch()?)
+        } else {
+            None
+        };
+        Some(ast::Expression::IfExpression(ast::IfExpression::new(
+            if_token,
+            Box::new(condition),
+            consequence,
+            alternative,
+        )))
+    }
+    /// Parses the branch following `else`. An `else if` chain is represented as
+    /// an alternative block holding the nested `if` expression.
+    fn parse_else_branch(&mut self) -> Option<ast::BlockStatement> {
+        if !self.peek_token.is_type(TokenType::If) {
+            if !self.expect_peek(TokenType::LeftBrace) {
+                return None;
+            }
+            return self.parse_block_statement();
+        }
+        let else_token = self.current_token.clone();
+        self.advance();
+        let if_token = self.current_token.clone();
+        let nested_if = self.parse_if_expression()?;
+        let end = nested_if.span();
+        Some(ast::BlockStatement::new(
+            else_token,
+            vec![ast::Statement::ExpressionStmt(ast::ExpressionStmt {
+                token: if_token,
+                expression: nested_if,
+            })],
+            end,
         ))
     }
-    fn parse_grouped_expression(&mut self) -> ast::Expression {
-        todo!()
+    fn parse_block_statement(&mut self) -> Option<ast::BlockStatement> {
+        let block_token = self.current_token.clone();
+        self.advance();
+        let statements = self.parse_statement_list(TokenType::RightBrace);
+        if self.current_token.is_type(TokenType::Eof) {
+            self.errors
+                .push(ParseError::UnterminatedBlock { open: block_token });
+            return None;
+        }
+        Some(ast::BlockStatement::new(
+            block_token,
+            statements,
+            self.current_token.span,
+        ))
+    }
+    fn parse_function(&mut self) -> Option<ast::Expression> {
+        let function_token = self.current_token.clone();
+        if !self.expect_peek(TokenType::LeftParen) {
+            return None;
+        }
+        let parameters = self.parse_function_parameters()?;
+        if !self.expect_peek(TokenType::LeftBrace) {
+            return None;
+        }
+        let body = self.parse_block_statement()?;
+        Some(ast::Expression::FunctionLiteral(ast::FunctionLiteral::new(
+            function_token,
+            parameters,
+            body,
+        )))
+    }
+    fn parse_function_parameters(&mut self) -> Option<Vec<ast::Identifier>> {
+        let mut parameters = vec![];
+        if self.peek_token.is_type(TokenType::RightParen) {
+            self.advance();
+            return Some(parameters);
+        }
+        if !self.expect_peek(TokenType::Identifier) {
+            return None;
+        }
+        parameters.push(ast::Identifier::new(&self.current_token));
+        while self.peek_token.is_type(TokenType::Comma) {
+            self.advance();
+            if !self.expect_peek(TokenType::Identifier) {
+                return None;
+            }
+            parameters.push(ast::Identifier::new(&self.current_token));
+        }
+        if !self.expect_peek(TokenType::RightParen) {
+            return None;
+        }
+        Some(parameters)
+    }
+    fn parse_call_expression(&mut self, function: ast::Expression) -> Option<ast::Expression> {
+        let call_token = self.current_token.clone();
+        let arguments = self.parse_expression_list(TokenType::RightParen)?;
+        Some(ast::Expression::CallExpression(ast::CallExpression::new(
+            call_token,
+            Box::new(function),
+            arguments,
+            self.current_token.span,
+        )))
     }
-    fn parse_if_expression(&mut self) -> ast::Expression {
-        todo!()
+    fn parse_array_literal(&mut self) -> Option<ast::Expression> {
+        let array_token = self.current_token.clone();
+        let elements = self.parse_expression_list(TokenType::RightBracket)?;
+        Some(ast::Expression::ArrayLiteral(ast::ArrayLiteral::new(
+            array_token,
+            elements,
+            self.current_token.span,
+        )))
     }
-    fn parse_function(&mut self) -> ast::Expression {
-        todo!()
+    /// A `{` in expression position always opens a hash literal. Block
+    /// statements only follow `if (...)`, `else` and `fn(...)`, which parse
+    /// their bodies with `parse_block_statement` directly.
+    fn parse_hash_literal(&mut self) -> Option<ast::Expression> {
+        let hash_token = self.current_token.clone();
+        let mut pairs = vec![];
+        while !self.peek_token.is_type(TokenType::RightBrace) {
+            self.advance();
+            let key = self.parse_expression(Precedence::Lowest)?;
+            if !self.expect_peek(TokenType::Colon) {
+                return None;
+            }
+            self.advance();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+            if !self.peek_token.is_type(TokenType::RightBrace)
+                && !self.expect_peek(TokenType::Comma)
+            {
+                return None;
+            }
+        }
+        self.advance();
+        Some(ast::Expression::HashLiteral(ast::HashLiteral::new(
+            hash_token,
+            pairs,
+            self.current_token.span,
+        )))
+    }
+    fn parse_index_expression(&mut self, left: ast::Expression) -> Option<ast::Expression> {
+        let index_token = self.current_token.clone();
+        self.advance();
+        let index = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RightBracket) {
+            return None;
+        }
+        Some(ast::Expression::IndexExpression(ast::IndexExpression::new(
+            index_token,
+            Box::new(left),
+            Box::new(index),
+            self.current_token.span,
+        )))
+    }
+    fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<ast::Expression>> {
+        let mut list = vec![];
+        if self.peek_token.is_type(end) {
+            self.advance();
+            return Some(list);
+        }
+        self.advance();
+        list.push(self.parse_expression(Precedence::Lowest)?);
+        while self.peek_token.is_type(TokenType::Comma) {
+            self.advance();
+            self.advance();
+            list.push(self.parse_expression(Precedence::Lowest)?);
+        }
+        if !self.expect_peek(end) {
+            return None;
+        }
+        Some(list)
     }
 
     fn expect_peek(&mut self, token_type: TokenType) -> bool {
@@ -150,88 +549,187 @@ impl<'a> Parser<'a> {
         }
     }
     fn peek_error(&mut self, token_type: TokenType) {
-        let message = format!(
-            "expected next token to be {:?}, got {:?} instead",
-            token_type, self.peek_token.token_type
-        );
-        self.errors.push(message);
+        self.errors.push(ParseError::UnexpectedToken {
+            expected: token_type,
+            found: self.peek_token.clone(),
+        });
+    }
+
+    fn no_prefix_parse_fn_error(&mut self) {
+        let token = self.current_token.clone();
+        self.errors.push(if token.is_type(TokenType::Illegal) {
+            ParseError::IllegalCharacter { token }
+        } else {
+            ParseError::MissingPrefixParseFn { found: token }
+        });
+    }
+
+    fn peek_precedence(&self) -> Precedence {
+        self.precedences
+            .get(&self.peek_token.token_type)
+            .copied()
+            .unwrap_or(Precedence::Lowest)
+    }
+
+    fn current_precedence(&self) -> Precedence {
+        self.precedences
+            .get(&self.current_token.token_type)
+            .copied()
+            .unwrap_or(Precedence::Lowest)
     }
 
     fn advance(&mut self) {
         self.current_token = std::mem::replace(&mut self.peek_token, self.lexer.next_token());
+        self.collect_lex_errors();
+    }
+
+    fn collect_lex_errors(&mut self) {
+        self.errors
+            .extend(self.lexer.take_errors().into_iter().map(ParseError::Lex));
     }
 
     fn parse_let_statement(&mut self) -> Option<ast::LetStmt> {
-        if !self.peek_token.is_type(TokenType::Identifier) {
-            return None;
-        }
+        let let_token = self.current_token.clone();
         if !self.expect_peek(TokenType::Identifier) {
             return None;
         }
-        let name = ast::Identifier::new(&self.current_token.literal);
+        let name = ast::Identifier::new(&self.current_token);
         if !self.expect_peek(TokenType::Assign) {
             return None;
         }
-        while !self.current_token.is_type(TokenType::Semicolon) {
+        self.advance();
+        let value = self.parse_expression(Precedence::Lowest)?;
+        if self.peek_token.is_type(TokenType::Semicolon) {
             self.advance();
         }
-        let statement = ast::LetStmt::new(name, None);
-        Some(statement)
+        Some(ast::LetStmt::new(let_token, name, value))
+    }
+
+    fn parse_assign_statement(&mut self) -> Option<ast::AssignStatement> {
+        let name = ast::Identifier::new(&self.current_token);
+        self.advance();
+        let operator_token = self.current_token.clone();
+        self.advance();
+        let value = self.parse_expression(Precedence::Lowest)?;
+        if self.peek_token.is_type(TokenType::Semicolon) {
+            self.advance();
+        }
+        Some(ast::AssignStatement::new(operator_token, name, value))
     }
 
     fn parse_return_statement(&mut self) -> Option<ast::ReturnStmt> {
-        let return_statement = ast::ReturnStmt::new(None);
-        while !self.current_token.is_type(TokenType::Semicolon) {
+        let return_token = self.current_token.clone();
+        self.advance();
+        let return_value = self.parse_expression(Precedence::Lowest)?;
+        if self.peek_token.is_type(TokenType::Semicolon) {
             self.advance();
         }
-        Some(return_statement)
+        Some(ast::ReturnStmt::new(return_token, return_value))
     }
 
-    fn parse_expression(&mut self) -> Option<ast::Expression> {
-        let prefix = self.prefix_parse_fns.get(&self.current_token.token_type)?;
-        match *prefix {
-            PrefixParser::Identifier => Some(self.parse_identifier()),
-            PrefixParser::Integer => Some(self.parse_integer_literal()),
-            PrefixParser::Boolean => Some(self.parse_boolean()),
-            PrefixParser::Prefix => Some(self.parse_prefix_expression()),
-            _ => None,
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<ast::Expression> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            self.errors.push(ParseError::NestingTooDeep {
+                token: self.current_token.clone(),
+            });
+            return None;
+        }
+        self.depth += 1;
+        let expression = self.parse_operator_expression(precedence);
+        self.depth -= 1;
+        expression
+    }
+
+    fn parse_operator_expression(&mut self, precedence: Precedence) -> Option<ast::Expression> {
+        let Some(&prefix) = self.prefix_parse_fns.get(&self.current_token.token_type) else {
+            self.no_prefix_parse_fn_error();
+            return None;
+        };
+        let mut left = self.parse_prefix(prefix)?;
+        while !self.peek_token.is_type(TokenType::Semicolon) && precedence < self.peek_precedence()
+        {
+            let Some(&infix) = self.infix_parse_fns.get(&self.peek_token.token_type) else {
+                return Some(left);
+            };
+            self.advance();
+            left = self.parse_infix(infix, left)?;
         }
+        Some(left)
     }
 
     fn parse_expression_statement(&mut self) -> Option<ast::ExpressionStmt> {
-        if let Some(expression) = self.parse_expression() {
-            Some(ast::ExpressionStmt {
-                token: self.current_token.clone(),
-                expression,
-            })
-        } else {
-            None
+        let token = self.current_token.clone();
+        let expression = self.parse_expression(Precedence::Lowest)?;
+        if self.peek_token.is_type(TokenType::Semicolon) {
+            self.advance();
         }
+        Some(ast::ExpressionStmt { token, expression })
     }
 
     fn parse_statement(&mut self) -> Option<ast::Statement> {
         match self.current_token.token_type {
-            TokenType::Let => self
-                .parse_let_statement()
-                .map(|stmt| ast::Statement::LetStmt(stmt)),
+            TokenType::Let => self.parse_let_statement().map(ast::Statement::LetStmt),
+            TokenType::Identifier if is_assignment(self.peek_token.token_type) => self
+                .parse_assign_statement()
+                .map(ast::Statement::AssignStatement),
             TokenType::Return => self
                 .parse_return_statement()
-                .map(|stmt| ast::Statement::ReturnStmt(stmt)),
+                .map(ast::Statement::ReturnStmt),
             _ => self
                 .parse_expression_statement()
-                .map(|stmt| ast::Statement::ExpressionStmt(stmt)),
+                .map(ast::Statement::ExpressionStmt),
         }
     }
 
-    pub fn parse_program(&mut self) -> Option<ast::Program> {
-        let mut program = ast::Program::default();
-        while self.current_token.token_type != TokenType::Eof {
+    /// Parses statements up to `end` or the end of input. A statement that
+    /// fails to parse is skipped via `synchronize` so later statements still
+    /// get parsed and report their own errors.
+    fn parse_statement_list(&mut self, end: TokenType) -> Vec<ast::Statement> {
+        let mut statements = vec![];
+        while !self.current_token.is_type(end) && !self.current_token.is_type(TokenType::Eof) {
+            if self.current_token.is_type(TokenType::Semicolon) {
+                self.advance();
+                continue;
+            }
+            let statement_start = self.current_token.span.start;
             if let Some(statement) = self.parse_statement() {
-                program.statements.push(statement);
+                statements.push(statement);
+                self.advance();
+            } else {
+                self.synchronize(statement_start);
             }
+        }
+        statements
+    }
+
+    /// Skips to the start of the next statement: just past a `;`, or at a
+    /// `let`, `return` or closing `}` outside of any nested braces.
+    fn synchronize(&mut self, statement_start: usize) {
+        if self.current_token.span.start == statement_start {
             self.advance();
         }
-        Some(program)
+        let mut depth = 0usize;
+        loop {
+            match self.current_token.token_type {
+                TokenType::Eof => return,
+                TokenType::Let | TokenType::Return | TokenType::RightBrace if depth == 0 => return,
+                TokenType::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                TokenType::LeftBrace => depth += 1,
+                TokenType::RightBrace => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Parses the whole input. The parser recovers from errors, so this
+    /// always returns a program, which is partial when `errors` is not empty.
+    pub fn parse_program(&mut self) -> ast::Program {
+        let statements = self.parse_statement_list(TokenType::Eof);
+        ast::Program { statements }
     }
 }
 
@@ -252,11 +750,15 @@ mod test {
             parser.errors.is_empty(),
             "parser has {} errors:\n{}",
             parser.errors.len(),
-            parser.errors.join("\n")
+            parser
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
         );
-        assert!(program.is_some(), "parser.parse_program returned None");
         assert_eq!(
-            program.unwrap().statements.len(),
+            program.statements.len(),
             3,
             "program.statements dows not contain three statements"
         );
@@ -271,18 +773,119 @@ mod test {
         ";
         let mut parser = Parser::new(input);
         let program = parser.parse_program();
-        assert!(program.is_some(), "parser.parse_program returned None");
-        assert!(
-            program.unwrap().statements.len() == 3,
-            "expected 3 statements"
-        );
+        assert!(program.statements.len() == 3, "expected 3 statements");
+    }
+
+    #[test]
+    fn test_let_statement_values() {
+        let test_cases = [
+            ("let x = 5;", "x", "5"),
+            ("let y = true;", "y", "true"),
+            ("let foobar = y", "foobar", "y"),
+            ("let z = a + b * c;", "z", "(a + (b * c))"),
+        ];
+        for (input, expected_name, expected_value) in test_cases {
+            let mut parser = Parser::new(input);
+            let statements = parser.parse_program().statements;
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(statements.len(), 1);
+            let Some(ast::Statement::LetStmt(let_stmt)) = statements.first() else {
+                panic!("Expected LetStatement")
+            };
+            assert_eq!(let_stmt.name.value, expected_name);
+            assert_eq!(let_stmt.value.to_string(), expected_value);
+        }
+    }
+
+    #[test]
+    fn test_return_statement_values() {
+        let test_cases = [
+            ("return 5;", "5"),
+            ("return true;", "true"),
+            ("return foobar", "foobar"),
+            ("return double(5);", "double(5)"),
+        ];
+        for (input, expected_value) in test_cases {
+            let mut parser = Parser::new(input);
+            let statements = parser.parse_program().statements;
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(statements.len(), 1);
+            let Some(ast::Statement::ReturnStmt(return_stmt)) = statements.first() else {
+                panic!("Expected ReturnStatement")
+            };
+            assert_eq!(return_stmt.return_value.to_string(), expected_value);
+        }
+    }
+
+    #[test]
+    fn test_assign_statements() {
+        let test_cases = [
+            ("x = 5;", "x", "=", None, "5"),
+            ("x += 1", "x", "+=", Some("+"), "1"),
+            ("y -= a * b;", "y", "-=", Some("-"), "(a * b)"),
+            ("z *= 2", "z", "*=", Some("*"), "2"),
+            ("z /= f(2)", "z", "/=", Some("/"), "f(2)"),
+        ];
+        for (input, name, operator, infix_operator, value) in test_cases {
+            let mut parser = Parser::new(input);
+            let statements = parser.parse_program().statements;
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(statements.len(), 1);
+            let Some(ast::Statement::AssignStatement(assign)) = statements.first() else {
+                panic!("Expected AssignStatement")
+            };
+            assert_eq!(assign.name.value, name);
+            assert_eq!(assign.operator, operator);
+            assert_eq!(assign.infix_operator(), infix_operator);
+            assert_eq!(assign.value.to_string(), value);
+        }
+    }
+
+    #[test]
+    fn test_assign_statement_errors() {
+        let test_cases = [
+            ("x = ;", "expected expression, found `;`"),
+            ("5 = 1", "expected expression, found `=`"),
+            ("x == 1 = 2", "expected expression, found `=`"),
+        ];
+        for (input, message) in test_cases {
+            let mut parser = Parser::new(input);
+            parser.parse_program();
+            let error = parser.errors.first().unwrap_or_else(|| panic!("{input}"));
+            assert_eq!(error.to_string(), message, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// leading\nlet x = 1; /* between */ x // trailing";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(program.to_string(), "let x = 1;\nx");
+    }
+
+    #[test]
+    fn test_statement_display() {
+        let input = "let x = 1 + 2; return x";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert_eq!(program.to_string(), "let x = (1 + 2);\nreturn x;");
+    }
+
+    #[test]
+    fn test_assign_statement_display() {
+        let input = "let x = 1; x *= 3; x = x - 1";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert_eq!(program.to_string(), "let x = 1;\nx *= 3;\nx = (x - 1);");
     }
 
     #[test]
     fn test_identifier_statement() {
         let input = "foobar;";
         let mut parser = Parser::new(input);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program();
         assert_eq!(program.statements.len(), 1,);
         if let Some(statement) = program.statements.first() {
             if let ast::Statement::ExpressionStmt(expr) = statement {
@@ -303,7 +906,7 @@ mod test {
     fn test_integer_literals() {
         let input = "5;";
         let mut parser = Parser::new(input);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program();
         assert_eq!(program.statements.len(), 1,);
         if let Some(statement) = program.statements.first() {
             if let ast::Statement::ExpressionStmt(expr) = statement {
@@ -327,13 +930,13 @@ mod test {
         false;
         ";
         let mut parser = Parser::new(input);
-        let program = parser.parse_program().unwrap();
+        let program = parser.parse_program();
         assert_eq!(program.statements.len(), 2,);
         let mut statements = program.statements.iter();
         if let Some(statement) = statements.next() {
             if let ast::Statement::ExpressionStmt(expr) = statement {
                 if let ast::Expression::Boolean(boolean) = &expr.expression {
-                    assert_eq!(boolean.value, true);
+                    assert!(boolean.value);
                 } else {
                     panic!("Expected true")
                 }
@@ -346,7 +949,7 @@ mod test {
         if let Some(statement) = statements.next() {
             if let ast::Statement::ExpressionStmt(expr) = statement {
                 if let ast::Expression::Boolean(boolean) = &expr.expression {
-                    assert_eq!(boolean.value, false);
+                    assert!(!boolean.value);
                 } else {
                     panic!("Expected false")
                 }
@@ -363,8 +966,481 @@ mod test {
         let test_cases = [("!5", "!", 5), ("-15", "-", 15)];
         for (input, operator, integer_value) in test_cases {
             let mut parser = Parser::new(input);
-            let statements = parser.parse_program().unwrap().statements;
+            let statements = parser.parse_program().statements;
             assert_eq!(statements.len(), 1);
+            let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+                panic!("Expected ExpressionStatement")
+            };
+            let ast::Expression::PrefixExpression(prefix) = &expr.expression else {
+                panic!("Expected PrefixExpression")
+            };
+            assert_eq!(prefix.operator, operator);
+            let ast::Expression::IntegerLiteral(integer_literal) = prefix.right.as_ref() else {
+                panic!("Expected IntegerLiteral")
+            };
+            assert_eq!(integer_literal.value, integer_value);
+        }
+    }
+
+    #[test]
+    fn test_infix_expression() {
+        let test_cases = [
+            ("5 + 5;", 5, "+", 5),
+            ("5 - 5;", 5, "-", 5),
+            ("5 * 5;", 5, "*", 5),
+            ("5 / 5;", 5, "/", 5),
+            ("5 > 5;", 5, ">", 5),
+            ("5 >= 5;", 5, ">=", 5),
+            ("5 < 5;", 5, "<", 5),
+            ("5 <= 5;", 5, "<=", 5),
+            ("5 == 5;", 5, "==", 5),
+            ("5 != 5;", 5, "!=", 5),
+        ];
+        for (input, left_value, operator, right_value) in test_cases {
+            let mut parser = Parser::new(input);
+            let statements = parser.parse_program().statements;
+            assert_eq!(statements.len(), 1);
+            let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+                panic!("Expected ExpressionStatement")
+            };
+            let ast::Expression::InfixExpression(infix) = &expr.expression else {
+                panic!("Expected InfixExpression, got {}", expr.expression)
+            };
+            assert_eq!(infix.operator, operator);
+            let ast::Expression::IntegerLiteral(left) = infix.left.as_ref() else {
+                panic!("Expected IntegerLiteral")
+            };
+            assert_eq!(left.value, left_value);
+            let ast::Expression::IntegerLiteral(right) = infix.right.as_ref() else {
+                panic!("Expected IntegerLiteral")
+            };
+            assert_eq!(right.value, right_value);
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let test_cases = [
+            ("-a * b", "((-a) * b)"),
+            ("!-a", "(!(-a))"),
+            ("a + b + c", "((a + b) + c)"),
+            ("a * b / c", "((a * b) / c)"),
+            ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
+            ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
+            ("5 >= 4 != 3 <= 4", "((5 >= 4) != (3 <= 4))"),
+            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+            ("-(5 + 5)", "(-(5 + 5))"),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(program.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let input = "if (x < y) { x }";
+        let mut parser = Parser::new(input);
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(statements.len(), 1);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::IfExpression(if_expression) = &expr.expression else {
+            panic!("Expected IfExpression, got {}", expr.expression)
+        };
+        assert_eq!(if_expression.condition.to_string(), "(x < y)");
+        assert_eq!(if_expression.consequence.statements.len(), 1);
+        assert_eq!(if_expression.consequence.statements[0].to_string(), "x");
+        assert!(if_expression.alternative.is_none());
+    }
+
+    #[test]
+    fn test_if_else_expression() {
+        let test_cases = [
+            ("if (x < y) { x } else { y }", "if (x < y) { x } else { y }"),
+            (
+                "if (x < y) { x } else if (x > y) { y } else { 0 }",
+                "if (x < y) { x } else { if (x > y) { y } else { 0 } }",
+            ),
+            ("if (x) {} else {}", "if x {} else {}"),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(program.statements.len(), 1);
+            assert_eq!(program.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_block() {
+        let mut parser = Parser::new("if (x) { x");
+        let program = parser.parse_program();
+        assert!(program.statements.is_empty());
+        assert_eq!(parser.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_function_literal() {
+        let input = "fn(x, y) { x + y; }";
+        let mut parser = Parser::new(input);
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(statements.len(), 1);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::FunctionLiteral(function) = &expr.expression else {
+            panic!("Expected FunctionLiteral, got {}", expr.expression)
+        };
+        let parameters: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.value.as_str())
+            .collect();
+        assert_eq!(parameters, ["x", "y"]);
+        assert_eq!(function.body.statements.len(), 1);
+        assert_eq!(function.body.statements[0].to_string(), "(x + y)");
+    }
+
+    #[test]
+    fn test_function_parameters() {
+        let test_cases: [(&str, &[&str]); 3] = [
+            ("fn() {};", &[]),
+            ("fn(x) {};", &["x"]),
+            ("fn(x, y, z) {};", &["x", "y", "z"]),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let statements = parser.parse_program().statements;
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+                panic!("Expected ExpressionStatement")
+            };
+            let ast::Expression::FunctionLiteral(function) = &expr.expression else {
+                panic!("Expected FunctionLiteral, got {}", expr.expression)
+            };
+            let parameters: Vec<&str> = function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.value.as_str())
+                .collect();
+            assert_eq!(parameters, expected);
+        }
+    }
+
+    #[test]
+    fn test_call_expression() {
+        let input = "add(1, 2 * 3, 4 + 5);";
+        let mut parser = Parser::new(input);
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        assert_eq!(statements.len(), 1);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::CallExpression(call) = &expr.expression else {
+            panic!("Expected CallExpression, got {}", expr.expression)
+        };
+        assert_eq!(call.function.to_string(), "add");
+        let arguments: Vec<String> = call.arguments.iter().map(ToString::to_string).collect();
+        assert_eq!(arguments, ["1", "(2 * 3)", "(4 + 5)"]);
+    }
+
+    #[test]
+    fn test_call_precedence() {
+        let test_cases = [
+            ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
+            (
+                "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+                "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
+            ),
+            (
+                "add(a + b + c * d / f + g)",
+                "add((((a + b) + ((c * d) / f)) + g))",
+            ),
+            ("fn(x) { x }(5)", "fn(x) { x }(5)"),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(program.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let input = "let add = fn(a, b) {\n  a + b\n};\nadd(1, -2)";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let spans: Vec<&str> = program
+            .statements
+            .iter()
+            .map(|statement| &input[statement.span().start..statement.span().end])
+            .collect();
+        assert_eq!(spans, ["let add = fn(a, b) {\n  a + b\n}", "add(1, -2)"]);
+
+        let Some(ast::Statement::LetStmt(let_stmt)) = program.statements.first() else {
+            panic!("Expected LetStatement")
+        };
+        let ast::Expression::FunctionLiteral(function) = &let_stmt.value else {
+            panic!("Expected FunctionLiteral")
+        };
+        let sum = &function.body.statements[0];
+        assert_eq!(&input[sum.span().start..sum.span().end], "a + b");
+        assert_eq!((sum.span().line, sum.span().column), (2, 3));
+
+        let Some(ast::Statement::ExpressionStmt(call)) = program.statements.last() else {
+            panic!("Expected ExpressionStatement")
+        };
+        assert_eq!((call.span().line, call.span().column), (4, 1));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let test_cases = [
+            ("let = 5;", "E0001", "expected identifier, found `=`"),
+            ("let x 5;", "E0001", "expected `=`, found integer `5`"),
+            ("let fn = 5;", "E0001", "expected identifier, found `fn`"),
+            ("(1 + 2", "E0001", "expected `)`, found end of input"),
+            ("1 + ;", "E0002", "expected expression, found `;`"),
+            (
+                "99999999999999999999",
+                "E0003",
+                "integer literal `99999999999999999999` is too large",
+            ),
+            ("fn() { 1", "E0004", "unterminated block"),
+            ("@", "E0005", "unexpected character `@`"),
+            ("1 /* 2", "E0009", "unterminated block comment"),
+        ];
+        for (input, code, message) in test_cases {
+            let mut parser = Parser::new(input);
+            parser.parse_program();
+            let error = parser.errors.first().unwrap_or_else(|| panic!("{input}"));
+            assert_eq!((error.code(), error.to_string().as_str()), (code, message));
+        }
+    }
+
+    #[test]
+    fn test_parse_error_diagnostic() {
+        let input = "let x = 1;\nlet y = (x + 2;";
+        let mut parser = Parser::new(input);
+        parser.parse_program();
+        let expected = "\
+error[E0001]: expected `)`, found `;`
+ --> 2:15
+  |
+2 | let y = (x + 2;
+  |               ^ expected `)`
+  |
+  = help: insert `)` before `;`";
+        assert_eq!(parser.errors[0].to_diagnostic().render(input), expected);
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let input = "let x 5; let = 10; let y = 2; -; 99999999999999999999; if (y { 1 }; y";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        let codes: Vec<&str> = parser.errors.iter().map(ParseError::code).collect();
+        assert_eq!(codes, ["E0001", "E0001", "E0002", "E0003", "E0001"]);
+        let statements: Vec<String> = program.statements.iter().map(ToString::to_string).collect();
+        assert_eq!(statements, ["let y = 2;", "y"]);
+    }
+
+    #[test]
+    fn test_recovery_resumes_at_statement_keywords() {
+        let input = "let x = let y = 2; return; return 3";
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        assert_eq!(parser.errors.len(), 2);
+        let statements: Vec<String> = program.statements.iter().map(ToString::to_string).collect();
+        assert_eq!(statements, ["let y = 2;", "return 3;"]);
+    }
+
+    #[test]
+    fn test_malformed_input_terminates() {
+        let deeply_nested = "(".repeat(1_000);
+        let deep_prefix = format!("{}1", "-".repeat(1_000));
+        let inputs = [
+            "-;",
+            "let",
+            "let x",
+            "let x =",
+            "return",
+            "fn(",
+            "fn(x,",
+            "fn(x) {",
+            "if",
+            "if (",
+            "if (x) { 1 } else",
+            "add(1,",
+            ")",
+            "}",
+            "{",
+            "}}}; let",
+            deeply_nested.as_str(),
+            deep_prefix.as_str(),
+        ];
+        for input in inputs {
+            let mut parser = Parser::new(input);
+            parser.parse_program();
+            assert!(!parser.errors.is_empty(), "expected errors for {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let mut parser = Parser::new(r#""hello\tworld";"#);
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::StringLiteral(string) = &expr.expression else {
+            panic!("Expected StringLiteral, got {}", expr.expression)
+        };
+        assert_eq!(string.value, "hello\tworld");
+        assert_eq!(expr.expression.to_string(), r#""hello\tworld""#);
+    }
+
+    #[test]
+    fn test_string_errors() {
+        let input = "let a = \"ok\";\nlet s = \"abc + 1";
+        let mut parser = Parser::new(input);
+        parser.parse_program();
+        assert_eq!(parser.errors.len(), 1, "{:?}", parser.errors);
+        let expected = "\
+error[E0007]: unterminated string literal
+ --> 2:9
+  |
+2 | let s = \"abc + 1
+  |         ^ string starts here
+  |
+  = help: add a closing `\"`";
+        assert_eq!(parser.errors[0].to_diagnostic().render(input), expected);
+
+        let mut parser = Parser::new(r#""\q""#);
+        parser.parse_program();
+        let codes: Vec<&str> = parser.errors.iter().map(ParseError::code).collect();
+        assert_eq!(codes, ["E0008"]);
+    }
+
+    #[test]
+    fn test_array_literal() {
+        let mut parser = Parser::new("[1, 2 * 2, 3 + 3]");
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::ArrayLiteral(array) = &expr.expression else {
+            panic!("Expected ArrayLiteral, got {}", expr.expression)
+        };
+        let elements: Vec<String> = array.elements.iter().map(ToString::to_string).collect();
+        assert_eq!(elements, ["1", "(2 * 2)", "(3 + 3)"]);
+    }
+
+    #[test]
+    fn test_index_expression() {
+        let mut parser = Parser::new("myArray[1 + 1]");
+        let statements = parser.parse_program().statements;
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::IndexExpression(index) = &expr.expression else {
+            panic!("Expected IndexExpression, got {}", expr.expression)
+        };
+        assert_eq!(index.left.to_string(), "myArray");
+        assert_eq!(index.index.to_string(), "(1 + 1)");
+    }
+
+    #[test]
+    fn test_index_precedence() {
+        let test_cases = [
+            ("[]", "[]"),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
+            ("f(x)[0]", "(f(x)[0])"),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(program.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let test_cases = [
+            (
+                r#"{"one": 1, "two": 2, "three": 3}"#,
+                r#"{"one": 1, "two": 2, "three": 3}"#,
+            ),
+            ("{}", "{}"),
+            (
+                "{1: 0 + 1, true: 10 - 8, x: 15 / 5,}",
+                "{1: (0 + 1), true: (10 - 8), x: (15 / 5)}",
+            ),
+            (r#"{"a": {"b": [1]}}["a"]"#, r#"({"a": {"b": [1]}}["a"])"#),
+        ];
+        for (input, expected) in test_cases {
+            let mut parser = Parser::new(input);
+            let program = parser.parse_program();
+            assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+            assert_eq!(program.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_literal_pairs() {
+        let mut parser = Parser::new(r#"{"one": 1, "two": 2}"#);
+        let statements = parser.parse_program().statements;
+        let Some(ast::Statement::ExpressionStmt(expr)) = statements.first() else {
+            panic!("Expected ExpressionStatement")
+        };
+        let ast::Expression::HashLiteral(hash) = &expr.expression else {
+            panic!("Expected HashLiteral, got {}", expr.expression)
+        };
+        let pairs: Vec<(String, String)> = hash
+            .pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                (r#""one""#.to_string(), "1".to_string()),
+                (r#""two""#.to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_literal_errors() {
+        let test_cases = [
+            (r#"{"a" 1}"#, "expected `:`, found integer `1`"),
+            (r#"{"a": 1 "b": 2}"#, "expected `,`, found string \"b\""),
+        ];
+        for (input, message) in test_cases {
+            let mut parser = Parser::new(input);
+            parser.parse_program();
+            assert_eq!(parser.errors[0].to_string(), message);
         }
     }
 }
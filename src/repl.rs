@@ -0,0 +1,466 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::PoisonError;
+use std::time::Instant;
+
+use reedline::{
+    ColumnarMenu, DefaultPrompt, DefaultPromptSegment, KeyCode, KeyModifiers, MenuBuilder, Prompt,
+    PromptEditMode, PromptHistorySearch, Reedline, ReedlineEvent, ReedlineMenu, Signal,
+    ValidationResult, Validator, Vi, default_vi_insert_keybindings, default_vi_normal_keybindings,
+};
+
+use crate::ast;
+use crate::cli::{Engine, Failure};
+use crate::completer::{MonkeyCompleter, SessionNames};
+use crate::dump::{self, Tree};
+use crate::highlighter::{MonkeyHighlighter, THEME_VAR, Theme};
+use crate::lexer::{LexError, Lexer};
+use crate::object::Object;
+use crate::parser::{ParseError, Parser};
+use crate::session::Session;
+use crate::token::TokenType;
+
+/// Shown in front of every line after the first of a multi-line input,
+/// padded to the width of `monkey: ` so the code lines up.
+const CONTINUATION_PROMPT: &str = "   ...: ";
+
+/// The origin named in diagnostics for code typed at the prompt.
+const REPL: &str = "<repl>";
+
+/// The default prompt, with a continuation indicator of its own.
+struct ReplPrompt(DefaultPrompt);
+
+impl Prompt for ReplPrompt {
+    fn render_prompt_left(&self) -> Cow<'_, str> {
+        self.0.render_prompt_left()
+    }
+
+    fn render_prompt_right(&self) -> Cow<'_, str> {
+        self.0.render_prompt_right()
+    }
+
+    fn render_prompt_indicator(&self, prompt_mode: PromptEditMode) -> Cow<'_, str> {
+        self.0.render_prompt_indicator(prompt_mode)
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
+        Cow::Borrowed(CONTINUATION_PROMPT)
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<'_, str> {
+        self.0
+            .render_prompt_history_search_indicator(history_search)
+    }
+}
+
+/// Keeps the editor reading lines while the input is visibly unfinished:
+/// brackets left open, a string or block comment left unterminated, or a
+/// trailing operator still waiting for its operand.
+struct InputValidator;
+
+impl Validator for InputValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        if is_incomplete(line) {
+            ValidationResult::Incomplete
+        } else {
+            ValidationResult::Complete
+        }
+    }
+}
+
+fn is_incomplete(input: &str) -> bool {
+    // Two empty continuation lines submit whatever has been typed, so a
+    // stray bracket cannot trap the user in continuation mode.
+    if input.ends_with("\n\n") {
+        return false;
+    }
+    // Commands that take code read on like source does; the others fit on
+    // one line.
+    if let Some((name, argument)) = Meta::split(input) {
+        return Meta::CODE.contains(&name) && !argument.is_empty() && is_incomplete(argument);
+    }
+    let mut lexer = Lexer::new(input);
+    let mut depth = 0_usize;
+    let mut last = TokenType::Eof;
+    loop {
+        let token = lexer.next_token();
+        match token.token_type {
+            TokenType::Eof => break,
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => {
+                // Closing more than was opened cannot be fixed by reading
+                // on; let the parser report it.
+                let Some(outer) = depth.checked_sub(1) else {
+                    return false;
+                };
+                depth = outer;
+            }
+            _ => {}
+        }
+        last = token.token_type;
+    }
+    let unterminated = lexer.take_errors().iter().any(|error| {
+        matches!(
+            error,
+            LexError::UnterminatedString { .. } | LexError::UnterminatedComment { .. }
+        )
+    });
+    depth > 0 || unterminated || expects_operand(last)
+}
+
+/// Whether input ending in `token_type` still needs an operand.
+fn expects_operand(token_type: TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Assign
+            | TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Bang
+            | TokenType::Asterisk
+            | TokenType::Slash
+            | TokenType::PlusEqual
+            | TokenType::MinusEqual
+            | TokenType::AsteriskEqual
+            | TokenType::SlashEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::BangEqual
+            | TokenType::EqualEqual
+            | TokenType::Comma
+            | TokenType::Colon
+    )
+}
+
+const HELP: &str = "\
+:env              list the session's bindings
+:reset            clear every binding
+:save <file>      write the statements that built the session to <file>
+:load <file>      run <file> in the session
+:tokens <code>    show the tokens of <code>
+:ast <code>       show the syntax tree of <code>
+:bytecode <code>  show the instructions <code> compiles to
+:time <code>      run <code> and report how long it took
+:help             show this list";
+
+/// A command to the REPL itself, written as `:name` followed by its
+/// argument, if any. No Monkey program starts with a colon, so these never
+/// shadow source input.
+#[derive(Debug, PartialEq, Eq)]
+enum Meta {
+    Env,
+    Reset,
+    Save(PathBuf),
+    Load(PathBuf),
+    Tokens(String),
+    Ast(String),
+    Bytecode(String),
+    Time(String),
+    Help,
+}
+
+impl Meta {
+    /// The commands whose argument is Monkey source.
+    const CODE: [&str; 4] = ["tokens", "ast", "bytecode", "time"];
+
+    /// Splits a meta-command into its name and trimmed argument, or returns
+    /// `None` when `input` is Monkey source.
+    fn split(input: &str) -> Option<(&str, &str)> {
+        let command = input.trim().strip_prefix(':')?;
+        Some(match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        })
+    }
+
+    /// Parses `input` as a meta-command, or returns `None` when it is Monkey
+    /// source.
+    fn parse(input: &str) -> Option<Result<Meta, String>> {
+        let (name, argument) = Meta::split(input)?;
+        let no_argument = |meta| {
+            if argument.is_empty() {
+                Ok(meta)
+            } else {
+                Err(format!("`:{name}` takes no arguments"))
+            }
+        };
+        let file = |meta: fn(PathBuf) -> Meta| {
+            if argument.is_empty() {
+                Err(format!("`:{name}` needs a file"))
+            } else {
+                Ok(meta(PathBuf::from(argument)))
+            }
+        };
+        let code = |meta: fn(String) -> Meta| {
+            if argument.is_empty() {
+                Err(format!("`:{name}` needs code"))
+            } else {
+                Ok(meta(argument.to_string()))
+            }
+        };
+        Some(match name {
+            "env" => no_argument(Meta::Env),
+            "reset" => no_argument(Meta::Reset),
+            "save" => file(Meta::Save),
+            "load" => file(Meta::Load),
+            "tokens" => code(Meta::Tokens),
+            "ast" => code(Meta::Ast),
+            "bytecode" => code(Meta::Bytecode),
+            "time" => code(Meta::Time),
+            "help" => no_argument(Meta::Help),
+            _ => Err(format!("unknown command `:{name}`")),
+        })
+    }
+}
+
+fn run_meta(meta: Meta, session: &mut Session) {
+    match meta {
+        Meta::Env => {
+            let bindings = session.bindings();
+            if bindings.is_empty() {
+                println!("no bindings");
+            }
+            for (name, value) in bindings {
+                println!("{name} = {value}");
+            }
+        }
+        Meta::Reset => session.reset(),
+        Meta::Save(path) => {
+            if let Err(error) = session.save(&path) {
+                println!("error: cannot write {}: {error}", path.display());
+            }
+        }
+        Meta::Load(path) => match fs::read_to_string(&path) {
+            Ok(source) => {
+                if let Err(failure) = session.execute(&source) {
+                    println!("{}", failure.render(&path.display().to_string(), &source));
+                }
+            }
+            Err(error) => println!("error: cannot read {}: {error}", path.display()),
+        },
+        Meta::Tokens(source) => {
+            let (tokens, errors) = dump::tokens(&source);
+            print!("{}", dump::tokens_text(&tokens));
+            for error in errors {
+                println!(
+                    "{}\n",
+                    ParseError::Lex(error).to_diagnostic().render(&source)
+                );
+            }
+        }
+        Meta::Ast(source) => {
+            if let Some(program) = parse(&source) {
+                print!("{}", Tree::from_program(&program).to_text());
+            }
+        }
+        Meta::Bytecode(source) => {
+            if let Some(program) = parse(&source) {
+                match session.disassemble(&program) {
+                    Ok(text) => print!("{text}"),
+                    Err(error) => println!("{}", Failure::Compile(error).render(REPL, &source)),
+                }
+            }
+        }
+        Meta::Time(source) => {
+            if let Some(program) = parse(&source) {
+                let start = Instant::now();
+                let result = session.run(&program, &source);
+                let elapsed = start.elapsed();
+                print_result(result, &source);
+                println!("time: {elapsed:?}");
+            }
+        }
+        Meta::Help => println!("{HELP}"),
+    }
+}
+
+/// Parses `source`, printing its diagnostics instead when it does not
+/// parse.
+fn parse(source: &str) -> Option<ast::Program> {
+    let mut parser = Parser::new(source);
+    let program = parser.parse_program();
+    if parser.errors.is_empty() {
+        return Some(program);
+    }
+    for error in &parser.errors {
+        println!("{}\n", error.to_diagnostic().render(source));
+    }
+    None
+}
+
+fn print_result(result: Result<Object, Failure>, source: &str) {
+    match result {
+        Ok(value) => println!("{value}"),
+        Err(Failure::Runtime(message)) => println!("ERROR: {message}"),
+        Err(failure) => println!("{}", failure.render(REPL, source)),
+    }
+}
+
+/// Points the completer at the names currently bound in `session`.
+fn refresh_names(names: &SessionNames, session: &Session) {
+    let mut names = names.lock().unwrap_or_else(PoisonError::into_inner);
+    *names = session
+        .bindings()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+}
+
+pub fn run(engine: Engine) {
+    let theme = Theme::from_env().unwrap_or_else(|message| {
+        eprintln!("warning: ignoring {THEME_VAR}: {message}");
+        Theme::default()
+    });
+    let mut session = Session::new(engine);
+    let names = SessionNames::default();
+    let mut insert_keybindings = default_vi_insert_keybindings();
+    insert_keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Tab,
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion_menu".to_string()),
+            ReedlineEvent::MenuNext,
+        ]),
+    );
+    let mut line_editor = Reedline::create()
+        .with_edit_mode(Box::new(Vi::new(
+            insert_keybindings,
+            default_vi_normal_keybindings(),
+        )))
+        .with_highlighter(Box::new(MonkeyHighlighter::new(theme)))
+        .with_validator(Box::new(InputValidator))
+        .with_completer(Box::new(MonkeyCompleter::new(names.clone())))
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(
+            ColumnarMenu::default().with_name("completion_menu"),
+        )));
+    let prompt = ReplPrompt(DefaultPrompt::new(
+        DefaultPromptSegment::Basic("monkey".to_string()),
+        DefaultPromptSegment::Empty,
+    ));
+    loop {
+        let sig = line_editor.read_line(&prompt);
+        match sig {
+            Ok(Signal::Success(buffer)) => {
+                if let Some(meta) = Meta::parse(&buffer) {
+                    match meta {
+                        Ok(meta) => run_meta(meta, &mut session),
+                        Err(message) => println!("error: {message}"),
+                    }
+                    refresh_names(&names, &session);
+                    continue;
+                }
+                if let Some(program) = parse(&buffer) {
+                    print_result(session.run(&program, &buffer), &buffer);
+                    refresh_names(&names, &session);
+                }
+            }
+            Ok(Signal::CtrlD | Signal::CtrlC) => {
+                println!("Aborted");
+                break;
+            }
+            // Without a terminal every read fails, so stop instead of spinning.
+            Err(error) => {
+                eprintln!("error: {error}");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_incomplete() {
+        let test_cases = [
+            ("", false),
+            ("let x = 5;", false),
+            ("let add = fn(a, b) {", true),
+            ("let add = fn(a, b) {\n  a + b", true),
+            ("let add = fn(a, b) {\n  a + b\n}", false),
+            ("puts(1,", true),
+            ("[1, 2", true),
+            ("{\"a\": ", true),
+            ("{\"a\": 1}", false),
+            ("1 +", true),
+            ("1 +\n2", false),
+            ("x ==", true),
+            ("let x =", true),
+            ("x +=", true),
+            ("!", true),
+            ("\"open", true),
+            ("\"open\nclosed\"", false),
+            ("/* still", true),
+            ("/* done */ 1", false),
+            ("1 // comment with (", false),
+            ("1 // trailing +", false),
+            ("}", false),
+            ("f())(", false),
+            ("fn() {\n\n", false),
+            (":env", false),
+            ("  :save session.monkey", false),
+            (":save f(", false),
+            (":bytecode", false),
+            (":bytecode fn(a) {", true),
+            (":bytecode fn(a) {\n  a\n}", false),
+            (":time f(", true),
+            (":time f(\n\n", false),
+            (":ast 1 +", true),
+            (":tokens \"open", true),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(is_incomplete(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_meta() {
+        let test_cases = [
+            (":env", Ok(Meta::Env)),
+            ("  :reset  ", Ok(Meta::Reset)),
+            (":save s.monkey", Ok(Meta::Save(PathBuf::from("s.monkey")))),
+            (
+                ":load  my session.monkey ",
+                Ok(Meta::Load(PathBuf::from("my session.monkey"))),
+            ),
+            (":save", Err("`:save` needs a file".to_string())),
+            (":load   ", Err("`:load` needs a file".to_string())),
+            (":env x", Err("`:env` takes no arguments".to_string())),
+            (":tokens let x", Ok(Meta::Tokens("let x".to_string()))),
+            (":ast  1 + 2 ", Ok(Meta::Ast("1 + 2".to_string()))),
+            (
+                ":bytecode fn(a) {\n  a\n}",
+                Ok(Meta::Bytecode("fn(a) {\n  a\n}".to_string())),
+            ),
+            (":time f(30)", Ok(Meta::Time("f(30)".to_string()))),
+            (":help", Ok(Meta::Help)),
+            (":time", Err("`:time` needs code".to_string())),
+            (":help me", Err("`:help` takes no arguments".to_string())),
+            (":quit", Err("unknown command `:quit`".to_string())),
+            (":", Err("unknown command `:`".to_string())),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(Meta::parse(input), Some(expected), "input: {input}");
+        }
+        assert_eq!(Meta::parse("let x = 1;"), None);
+        assert_eq!(Meta::parse("{\"a\": 1}"), None);
+    }
+
+    #[test]
+    fn test_help_lists_every_command() {
+        for line in HELP.lines() {
+            let command = line.split_whitespace().next().unwrap();
+            let parsed = Meta::parse(command).unwrap();
+            assert!(
+                !matches!(&parsed, Err(message) if message.starts_with("unknown")),
+                "{command}: {parsed:?}"
+            );
+        }
+    }
+}
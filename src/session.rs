@@ -0,0 +1,301 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::ast::{self, Node};
+use crate::cli::{Engine, Failure};
+use crate::compiler::{CompileError, Compiler};
+use crate::dump;
+use crate::evaluator;
+use crate::object::{Environment, Object};
+use crate::parser::Parser;
+use crate::symbol_table::SymbolTable;
+use crate::vm::Vm;
+
+/// The bindings built up by successive inputs, so that each input can use
+/// what the earlier ones defined. Which half of the state is in use depends
+/// on the engine: the evaluator keeps an environment, while the VM carries
+/// its symbol table, constants and globals from one compilation to the next.
+pub struct Session {
+    engine: Engine,
+    env: Rc<RefCell<Environment>>,
+    symbol_table: SymbolTable,
+    constants: Vec<Object>,
+    globals: Vec<Option<Object>>,
+    /// The source of every top-level statement of the inputs that ran
+    /// without error, in order, as written by `save`. Bindings can also come
+    /// from a `let` inside a block or from calls that assign to captured
+    /// variables, so running all of them again is what rebuilds the session.
+    /// An input that fails leaves no bindings behind and is not recorded.
+    statements: Vec<String>,
+}
+
+impl Session {
+    pub fn new(engine: Engine) -> Session {
+        Session {
+            engine,
+            env: Environment::new(),
+            symbol_table: SymbolTable::new(),
+            constants: vec![],
+            globals: vec![],
+            statements: vec![],
+        }
+    }
+
+    /// Forgets every binding and recorded statement.
+    pub fn reset(&mut self) {
+        *self = Session::new(self.engine);
+    }
+
+    /// Parses and runs `source`.
+    pub fn execute(&mut self, source: &str) -> Result<Object, Failure> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse_program();
+        if !parser.errors.is_empty() {
+            return Err(Failure::Parse(parser.errors));
+        }
+        self.run(&program, source)
+    }
+
+    /// Runs `program`, which was parsed from `source`, against the bindings
+    /// of the earlier inputs. If it fails part way, the bindings it made or
+    /// changed before the error are rolled back.
+    pub fn run(&mut self, program: &ast::Program, source: &str) -> Result<Object, Failure> {
+        let result = match self.engine {
+            Engine::Evaluator => {
+                let store = self.env.borrow().snapshot();
+                let result = evaluator::eval_program(program, &self.env);
+                if result.is_error() {
+                    self.env.borrow_mut().restore(store);
+                }
+                result
+            }
+            Engine::Vm => {
+                let mut compiler = self.compiler();
+                compiler.compile(program).map_err(Failure::Compile)?;
+                let symbol_table = compiler.symbol_table().clone();
+                let bytecode = compiler.bytecode();
+                let constants = bytecode.constants.clone();
+                let mut vm = Vm::new(bytecode).with_globals(self.globals.clone());
+                let result = vm.run();
+                if !result.is_error() {
+                    self.symbol_table = symbol_table;
+                    self.constants = constants;
+                    self.globals = vm.into_globals();
+                }
+                result
+            }
+        };
+        match result {
+            Object::Error(message) => Err(Failure::Runtime(message)),
+            value => {
+                self.record(program, source);
+                Ok(value)
+            }
+        }
+    }
+
+    /// A compiler that resolves names against the session's globals. A
+    /// program that fails to compile must not leave its names behind, so it
+    /// works on copies.
+    fn compiler(&self) -> Compiler {
+        Compiler::new_with_state(self.symbol_table.clone(), self.constants.clone())
+    }
+
+    /// Compiles `program` without running it and disassembles the result,
+    /// listing only the constants the program itself adds.
+    pub fn disassemble(&self, program: &ast::Program) -> Result<String, CompileError> {
+        let mut compiler = self.compiler();
+        compiler.compile(program)?;
+        Ok(dump::bytecode_text(
+            &compiler.bytecode(),
+            self.constants.len(),
+        ))
+    }
+
+    fn record(&mut self, program: &ast::Program, source: &str) {
+        for statement in &program.statements {
+            let span = statement.span();
+            if let Some(text) = source.get(span.start..span.end) {
+                self.statements.push(format!("{text};"));
+            }
+        }
+    }
+
+    /// Every binding of the session, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, Object)> {
+        match self.engine {
+            Engine::Evaluator => self.env.borrow().bindings(),
+            Engine::Vm => {
+                let mut bindings: Vec<_> = self
+                    .symbol_table
+                    .global_names()
+                    .into_iter()
+                    .zip(&self.globals)
+                    .filter_map(|(name, value)| Some((name, value.clone()?)))
+                    .collect();
+                bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+                bindings
+            }
+        }
+    }
+
+    /// Writes the session's statements to `path` as a Monkey script that
+    /// rebuilds its bindings when loaded.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        for statement in &self.statements {
+            writeln!(file, "{statement}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ENGINES: [Engine; 2] = [Engine::Evaluator, Engine::Vm];
+
+    fn names(session: &Session) -> Vec<String> {
+        session
+            .bindings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_bindings_persist_across_inputs() {
+        for engine in ENGINES {
+            let mut session = Session::new(engine);
+            let inputs = [
+                ("let x = 5;", None),
+                ("let double = fn(n) { n * 2 };", None),
+                ("x += 1;", None),
+                ("double(x)", Some(Object::Integer(12))),
+                ("let s = \"a\"; s", Some(Object::String("a".to_string()))),
+            ];
+            for (input, expected) in inputs {
+                let value = session.execute(input).unwrap();
+                if let Some(expected) = expected {
+                    assert_eq!(value, expected, "{engine:?}: {input}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_failed_input_keeps_earlier_bindings() {
+        for engine in ENGINES {
+            let mut session = Session::new(engine);
+            session.execute("let x = 1;").unwrap();
+            assert!(session.execute("let y = ;").is_err());
+            assert!(session.execute("let z = missing;").is_err());
+            assert_eq!(session.execute("x").unwrap(), Object::Integer(1));
+            assert!(session.execute("y").is_err(), "{engine:?}");
+            assert!(session.execute("let w = 2; x += 1; missing").is_err());
+            assert_eq!(session.execute("x").unwrap(), Object::Integer(1));
+            assert!(session.execute("w").is_err(), "{engine:?}");
+        }
+    }
+
+    #[test]
+    fn test_bindings() {
+        for engine in ENGINES {
+            let mut session = Session::new(engine);
+            session.execute("let b = 2; let a = [1]; a").unwrap();
+            assert_eq!(
+                session.bindings(),
+                [
+                    ("a".to_string(), Object::Array(vec![Object::Integer(1)])),
+                    ("b".to_string(), Object::Integer(2)),
+                ],
+                "{engine:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        for engine in ENGINES {
+            let mut session = Session::new(engine);
+            session.execute("let x = 1;").unwrap();
+            session.reset();
+            assert_eq!(names(&session), Vec::<String>::new());
+            assert!(session.execute("x").is_err(), "{engine:?}");
+            session.execute("let y = 2;").unwrap();
+            assert_eq!(names(&session), ["y"]);
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut session = Session::new(Engine::Vm);
+        session.execute("let x = 5; let y = 6;").unwrap();
+        let program = Parser::new("y + 1").parse_program();
+        let expected = "\
+0000 OpGetGlobal 1
+0003 OpConstant 2
+0006 OpAdd
+0007 OpPop
+
+constant 2: 1
+";
+        assert_eq!(session.disassemble(&program).unwrap(), expected);
+        // Disassembling defines nothing.
+        assert_eq!(session.disassemble(&program).unwrap(), expected);
+        assert_eq!(names(&session), ["x", "y"]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("monkeyrs-session-{}", std::process::id()));
+        for engine in ENGINES {
+            let mut session = Session::new(engine);
+            let inputs = [
+                "let add = fn(a, b) {\n  a + b\n};",
+                "let x = add(1, 2); x",
+                "let y = ;",
+                "let z = missing;",
+                "let w = 1; x += 1; let z = missing;",
+                "x *= 2; x",
+                "if (x > 1) { let y = x + 1; }",
+                "let count = fn() { let n = 0; fn() { n += 1; n } }; let next = count();",
+                "next();",
+            ];
+            for input in inputs {
+                let _ = session.execute(input);
+            }
+            session.save(&path).unwrap();
+            let saved = fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                saved,
+                "\
+let add = fn(a, b) {
+  a + b
+};
+let x = add(1, 2);
+x;
+x *= 2;
+x;
+if (x > 1) { let y = x + 1; };
+let count = fn() { let n = 0; fn() { n += 1; n } };
+let next = count();
+next();
+",
+                "{engine:?}"
+            );
+
+            let mut loaded = Session::new(engine);
+            loaded.execute(&saved).unwrap();
+            assert_eq!(loaded.execute("add(x, 1)").unwrap(), Object::Integer(7));
+            // Bindings made inside a block or through a closure come back too.
+            assert_eq!(loaded.execute("y").unwrap(), Object::Integer(7));
+            assert_eq!(loaded.execute("next()").unwrap(), Object::Integer(2));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolScope {
+    Global,
+    Local,
+    Free,
+    /// The name a function was bound to, referring to the function itself.
+    Function,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: SymbolScope,
+    pub index: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SymbolTable {
+    outer: Option<Box<SymbolTable>>,
+    store: HashMap<String, Symbol>,
+    num_definitions: usize,
+    /// Names a function scope binds with `let` somewhere in its body. A
+    /// nested function may refer to one before the `let` is compiled, as the
+    /// evaluator looks names up when they are used.
+    declared: HashSet<String>,
+    /// The outer symbols captured by this scope, in capture order.
+    pub free_symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    pub fn new_enclosed(outer: SymbolTable) -> SymbolTable {
+        SymbolTable {
+            outer: Some(Box::new(outer)),
+            ..SymbolTable::default()
+        }
+    }
+
+    /// Consumes an enclosed table, returning its outer table.
+    pub fn into_outer(self) -> Option<SymbolTable> {
+        self.outer.map(|outer| *outer)
+    }
+
+    /// Binds `name` in this scope. Redefining a name that is already bound
+    /// here reuses its slot, matching how `let` overwrites a binding in the
+    /// evaluator's environment.
+    pub fn define(&mut self, name: &str) -> Symbol {
+        let scope = if self.outer.is_some() {
+            SymbolScope::Local
+        } else {
+            SymbolScope::Global
+        };
+        if let Some(symbol) = self.store.get(name)
+            && symbol.scope == scope
+        {
+            return symbol.clone();
+        }
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope,
+            index: self.num_definitions,
+        };
+        self.num_definitions += 1;
+        self.store.insert(name.to_string(), symbol.clone());
+        symbol
+    }
+
+    /// Binds `name` in the outermost scope.
+    pub fn define_global(&mut self, name: &str) -> Symbol {
+        match &mut self.outer {
+            Some(outer) => outer.define_global(name),
+            None => self.define(name),
+        }
+    }
+
+    pub fn define_function_name(&mut self, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Function,
+            index: 0,
+        };
+        self.store.insert(name.to_string(), symbol.clone());
+        symbol
+    }
+
+    pub fn declare(&mut self, name: &str) {
+        self.declared.insert(name.to_string());
+    }
+
+    /// Stops `name` from referring to the function being compiled, so that
+    /// assigning to it rebinds the variable the function was bound to.
+    pub fn forget_function_name(&mut self, name: &str) {
+        if self
+            .store
+            .get(name)
+            .is_some_and(|symbol| symbol.scope == SymbolScope::Function)
+        {
+            self.store.remove(name);
+        }
+    }
+
+    fn define_free(&mut self, original: Symbol) -> Symbol {
+        let symbol = Symbol {
+            name: original.name.clone(),
+            scope: SymbolScope::Free,
+            index: self.free_symbols.len(),
+        };
+        self.free_symbols.push(original);
+        self.store.insert(symbol.name.clone(), symbol.clone());
+        symbol
+    }
+
+    /// Looks `name` up through the enclosing scopes. Locals of an enclosing
+    /// function are captured as free variables of this scope.
+    pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.store.get(name) {
+            return Some(symbol.clone());
+        }
+        let outer = self.outer.as_mut()?;
+        let symbol = if outer.declared.contains(name) && !outer.store.contains_key(name) {
+            outer.define(name)
+        } else {
+            outer.resolve(name)?
+        };
+        if symbol.scope == SymbolScope::Global {
+            Some(symbol)
+        } else {
+            Some(self.define_free(symbol))
+        }
+    }
+
+    /// The names of this scope's locals, indexed by slot.
+    pub fn local_names(&self) -> Vec<String> {
+        let mut names = vec![String::new(); self.num_definitions];
+        for symbol in self.store.values() {
+            if symbol.scope == SymbolScope::Local {
+                names[symbol.index].clone_from(&symbol.name);
+            }
+        }
+        names
+    }
+
+    /// The names of the outermost scope's bindings, indexed by slot.
+    pub fn global_names(&self) -> Vec<String> {
+        if let Some(outer) = &self.outer {
+            return outer.global_names();
+        }
+        let mut names = vec![String::new(); self.num_definitions];
+        for symbol in self.store.values() {
+            names[symbol.index].clone_from(&symbol.name);
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn symbol(name: &str, scope: SymbolScope, index: usize) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            scope,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_define() {
+        let mut global = SymbolTable::new();
+        assert_eq!(global.define("a"), symbol("a", SymbolScope::Global, 0));
+        assert_eq!(global.define("b"), symbol("b", SymbolScope::Global, 1));
+        assert_eq!(global.define("a"), symbol("a", SymbolScope::Global, 0));
+
+        let mut local = SymbolTable::new_enclosed(global);
+        assert_eq!(local.define("a"), symbol("a", SymbolScope::Local, 0));
+        assert_eq!(local.define("c"), symbol("c", SymbolScope::Local, 1));
+        assert_eq!(local.local_names(), ["a", "c"]);
+        assert_eq!(
+            local.define_global("d"),
+            symbol("d", SymbolScope::Global, 2)
+        );
+        assert_eq!(local.global_names(), ["a", "b", "d"]);
+    }
+
+    #[test]
+    fn test_resolve_free() {
+        let mut global = SymbolTable::new();
+        global.define("a");
+        let mut first = SymbolTable::new_enclosed(global);
+        first.define("b");
+        let mut second = SymbolTable::new_enclosed(first);
+        second.define("c");
+
+        assert_eq!(
+            second.resolve("a"),
+            Some(symbol("a", SymbolScope::Global, 0))
+        );
+        assert_eq!(second.resolve("b"), Some(symbol("b", SymbolScope::Free, 0)));
+        assert_eq!(
+            second.resolve("c"),
+            Some(symbol("c", SymbolScope::Local, 0))
+        );
+        assert_eq!(second.resolve("d"), None);
+        assert_eq!(second.free_symbols, [symbol("b", SymbolScope::Local, 0)]);
+    }
+
+    #[test]
+    fn test_resolve_declared_later() {
+        let mut outer = SymbolTable::new_enclosed(SymbolTable::new());
+        outer.define("a");
+        outer.declare("b");
+        let mut inner = SymbolTable::new_enclosed(outer);
+        assert_eq!(inner.resolve("b"), Some(symbol("b", SymbolScope::Free, 0)));
+        assert_eq!(inner.resolve("c"), None);
+        let mut outer = inner.into_outer().unwrap();
+        assert_eq!(outer.define("b"), symbol("b", SymbolScope::Local, 1));
+        assert_eq!(outer.local_names(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_forget_function_name() {
+        let mut table = SymbolTable::new_enclosed(SymbolTable::new());
+        table.define_function_name("f");
+        table.forget_function_name("f");
+        assert_eq!(table.resolve("f"), None);
+    }
+
+    #[test]
+    fn test_function_name_is_shadowed() {
+        let mut table = SymbolTable::new_enclosed(SymbolTable::new());
+        table.define_function_name("f");
+        assert_eq!(
+            table.resolve("f"),
+            Some(symbol("f", SymbolScope::Function, 0))
+        );
+        assert_eq!(table.define("f"), symbol("f", SymbolScope::Local, 0));
+        assert_eq!(table.resolve("f"), Some(symbol("f", SymbolScope::Local, 0)));
+    }
+}
@@ -1,13 +1,17 @@
-// use std::fmt;
+use std::fmt;
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum TokenType {
     Illegal,
     Eof,
 
+    // Trivia, only produced when the lexer is asked to keep comments
+    Comment,
+
     // Identifiers and Literals
     Identifier,
     Int,
+    String,
 
     // Operators
     Assign,
@@ -31,11 +35,14 @@ pub enum TokenType {
     // Delimiters
     Comma,
     Semicolon,
+    Colon,
 
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
 
     // Keywords
     Function,
@@ -47,22 +54,132 @@ pub enum TokenType {
     Return,
 }
 
-// impl fmt::Display for TokenType {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         write!(f, "{}", self.)
-//     }
-// }
+/// The reserved words, which the lexer never reads as identifiers.
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("fn", TokenType::Function),
+    ("let", TokenType::Let),
+    ("true", TokenType::True),
+    ("false", TokenType::False),
+    ("if", TokenType::If),
+    ("else", TokenType::Else),
+    ("return", TokenType::Return),
+];
+
+pub fn lookup_keyword(literal: &str) -> Option<TokenType> {
+    KEYWORDS
+        .iter()
+        .find(|(keyword, _)| *keyword == literal)
+        .map(|(_, token_type)| *token_type)
+}
+
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            TokenType::Illegal => "illegal character",
+            TokenType::Eof => "end of input",
+            TokenType::Comment => "comment",
+            TokenType::Identifier => "identifier",
+            TokenType::Int => "integer",
+            TokenType::String => "string",
+            TokenType::Assign => "`=`",
+            TokenType::Plus => "`+`",
+            TokenType::Minus => "`-`",
+            TokenType::Bang => "`!`",
+            TokenType::Asterisk => "`*`",
+            TokenType::Slash => "`/`",
+            TokenType::PlusEqual => "`+=`",
+            TokenType::MinusEqual => "`-=`",
+            TokenType::AsteriskEqual => "`*=`",
+            TokenType::SlashEqual => "`/=`",
+            TokenType::Greater => "`>`",
+            TokenType::GreaterEqual => "`>=`",
+            TokenType::Less => "`<`",
+            TokenType::LessEqual => "`<=`",
+            TokenType::BangEqual => "`!=`",
+            TokenType::EqualEqual => "`==`",
+            TokenType::Comma => "`,`",
+            TokenType::Semicolon => "`;`",
+            TokenType::Colon => "`:`",
+            TokenType::LeftParen => "`(`",
+            TokenType::RightParen => "`)`",
+            TokenType::LeftBrace => "`{`",
+            TokenType::RightBrace => "`}`",
+            TokenType::LeftBracket => "`[`",
+            TokenType::RightBracket => "`]`",
+            TokenType::Function => "`fn`",
+            TokenType::Let => "`let`",
+            TokenType::True => "`true`",
+            TokenType::False => "`false`",
+            TokenType::If => "`if`",
+            TokenType::Else => "`else`",
+            TokenType::Return => "`return`",
+        };
+        write!(f, "{description}")
+    }
+}
+
+/// A region of source text. `start` and `end` are byte offsets, while `line`
+/// and `column` are 1-based and locate `start`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns a span covering `self` through the end of `other`.
+    #[must_use]
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
+#[allow(clippy::struct_field_names)]
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
+    pub span: Span,
+    /// Comments preceding this token, if the lexer was asked to keep them.
+    pub leading_trivia: Vec<Token>,
 }
 
 impl Token {
     pub fn is_type(&self, target_type: TokenType) -> bool {
         self.token_type == target_type
     }
+
+    /// Describes the token for diagnostics, quoting its text where the type
+    /// alone is not specific enough.
+    pub fn describe(&self) -> String {
+        match self.token_type {
+            TokenType::Identifier => format!("identifier `{}`", self.literal),
+            TokenType::Int => format!("integer `{}`", self.literal),
+            TokenType::String => format!("string {:?}", self.literal),
+            TokenType::Illegal => format!("`{}`", self.literal),
+            token_type => token_type.to_string(),
+        }
+    }
 }
 
 impl Token {
@@ -70,6 +187,13 @@ impl Token {
         Token {
             token_type,
             literal: literal.to_string(),
+            span: Span::default(),
+            leading_trivia: vec![],
         }
     }
+
+    #[must_use]
+    pub fn with_span(self, span: Span) -> Token {
+        Token { span, ..self }
+    }
 }
@@ -0,0 +1,702 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::builtins;
+use crate::code::{self, Opcode};
+use crate::compiler::Bytecode;
+use crate::evaluator;
+use crate::object::{
+    Capture, Closure, CompiledFunction, HashPair, MAX_CALL_DEPTH, Object, Variable,
+};
+
+/// The stack's initial capacity. It grows past this as needed, since a
+/// literal or call may have any number of operands; only the call depth is
+/// limited.
+pub const STACK_SIZE: usize = 2048;
+
+#[derive(Debug)]
+struct Frame {
+    closure: Rc<Closure>,
+    ip: usize,
+    /// The height the stack returns to when the frame is popped.
+    base_pointer: usize,
+    locals: Vec<Variable>,
+}
+
+/// Executes bytecode. Operators, indexing and builtins share their
+/// implementation with the evaluator so both backends give the same results,
+/// and runtime errors are reported as the same `Object::Error` values.
+pub struct Vm {
+    constants: Vec<Object>,
+    global_names: Vec<String>,
+    globals: Vec<Option<Object>>,
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    last_popped: Object,
+}
+
+impl Vm {
+    pub fn new(bytecode: Bytecode) -> Vm {
+        let main = CompiledFunction {
+            instructions: bytecode.instructions,
+            num_parameters: 0,
+            local_names: vec![],
+            free_names: vec![],
+            captures: vec![],
+            source: String::new(),
+        };
+        let main = Closure {
+            function: Rc::new(main),
+            free: vec![],
+        };
+        Vm {
+            constants: bytecode.constants,
+            global_names: bytecode.global_names,
+            globals: vec![],
+            stack: Vec::with_capacity(STACK_SIZE),
+            frames: vec![Frame {
+                closure: Rc::new(main),
+                ip: 0,
+                base_pointer: 0,
+                locals: vec![],
+            }],
+            last_popped: Object::Null,
+        }
+    }
+
+    /// Starts from the globals left by an earlier run, as returned by
+    /// `into_globals`.
+    pub fn with_globals(mut self, globals: Vec<Option<Object>>) -> Vm {
+        self.globals = globals;
+        self
+    }
+
+    pub fn into_globals(self) -> Vec<Option<Object>> {
+        self.globals
+    }
+
+    /// Runs the program, returning the value of its last statement or the
+    /// runtime error that stopped it.
+    pub fn run(&mut self) -> Object {
+        match self.execute() {
+            Ok(()) => self.last_popped.clone(),
+            Err(error) => error,
+        }
+    }
+
+    fn execute(&mut self) -> Result<(), Object> {
+        while let Some((opcode, operands)) = self.fetch() {
+            match opcode {
+                Opcode::Constant => self.push(self.constants[operands[0]].clone()),
+                Opcode::Pop => self.last_popped = self.pop(),
+                Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Div
+                | Opcode::Equal
+                | Opcode::NotEqual
+                | Opcode::GreaterThan
+                | Opcode::GreaterEqual
+                | Opcode::LessThan
+                | Opcode::LessEqual => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let operator = infix_operator(opcode);
+                    let result = evaluator::eval_infix_expression(operator, &left, &right);
+                    self.push_result(result)?;
+                }
+                Opcode::True => self.push(Object::Boolean(true)),
+                Opcode::False => self.push(Object::Boolean(false)),
+                Opcode::Null => self.push(Object::Null),
+                Opcode::Minus | Opcode::Bang => {
+                    let right = self.pop();
+                    let operator = if opcode == Opcode::Minus { "-" } else { "!" };
+                    let result = evaluator::eval_prefix_expression(operator, &right);
+                    self.push_result(result)?;
+                }
+                Opcode::JumpNotTruthy => {
+                    if !self.pop().is_truthy() {
+                        self.current_frame().ip = operands[0];
+                    }
+                }
+                Opcode::Jump => self.current_frame().ip = operands[0],
+                Opcode::GetGlobal => {
+                    let value = self.global(operands[0])?;
+                    self.push(value);
+                }
+                Opcode::SetGlobal => {
+                    let index = operands[0];
+                    if self.globals.len() <= index {
+                        self.globals.resize(index + 1, None);
+                    }
+                    self.globals[index] = Some(self.pop());
+                }
+                Opcode::AssignGlobal => {
+                    let value = self.pop();
+                    self.assign_global(operands[0], value)?;
+                }
+                Opcode::GetLocal => {
+                    let value = self.local(operands[0])?;
+                    self.push(value);
+                }
+                Opcode::SetLocal => self.set_local(operands[0]),
+                Opcode::GetBuiltin => {
+                    self.push(Object::Builtin(builtins::BUILTINS[operands[0]]));
+                }
+                Opcode::GetFree => {
+                    let value = self.free(operands[0])?;
+                    self.push(value);
+                }
+                Opcode::AssignFree => self.assign_free(operands[0])?,
+                Opcode::CurrentClosure => {
+                    let closure = Rc::clone(&self.current_frame().closure);
+                    self.push(Object::Closure(closure));
+                }
+                Opcode::Array => {
+                    let elements = self.stack.split_off(self.stack.len() - operands[0]);
+                    self.push(Object::Array(elements));
+                }
+                Opcode::Hash => {
+                    let elements = self.stack.split_off(self.stack.len() - operands[0]);
+                    let hash = build_hash(elements)?;
+                    self.push(hash);
+                }
+                Opcode::Index => {
+                    let index = self.pop();
+                    let left = self.pop();
+                    let result = evaluator::eval_index_expression(&left, &index);
+                    self.push_result(result)?;
+                }
+                Opcode::Call => self.call(operands[0])?,
+                Opcode::ReturnValue => {
+                    let value = self.pop();
+                    if !self.return_from_frame(value) {
+                        return Ok(());
+                    }
+                }
+                Opcode::Return => {
+                    if !self.return_from_frame(Object::Null) {
+                        return Ok(());
+                    }
+                }
+                Opcode::Closure => self.push_closure(operands[0])?,
+            }
+        }
+        Ok(())
+    }
+
+    fn global(&self, index: usize) -> Result<Object, Object> {
+        match self.globals.get(index) {
+            Some(Some(value)) => Ok(value.clone()),
+            _ => Err(Object::Error(format!(
+                "identifier not found: {}",
+                self.global_names[index]
+            ))),
+        }
+    }
+
+    fn assign_global(&mut self, index: usize, value: Object) -> Result<(), Object> {
+        match self.globals.get_mut(index) {
+            Some(Some(slot)) => {
+                *slot = value;
+                Ok(())
+            }
+            _ => Err(Object::Error(format!(
+                "assignment to undeclared identifier: {}",
+                self.global_names[index]
+            ))),
+        }
+    }
+
+    fn local(&mut self, index: usize) -> Result<Object, Object> {
+        let frame = self.current_frame();
+        read(
+            &frame.locals[index],
+            &frame.closure.function.local_names[index],
+        )
+    }
+
+    fn set_local(&mut self, index: usize) {
+        let value = self.pop();
+        *self.current_frame().locals[index].borrow_mut() = Some(value);
+    }
+
+    fn free(&mut self, index: usize) -> Result<Object, Object> {
+        let closure = &self.current_frame().closure;
+        read(&closure.free[index], &closure.function.free_names[index])
+    }
+
+    fn assign_free(&mut self, index: usize) -> Result<(), Object> {
+        let value = self.pop();
+        let closure = &self.current_frame().closure;
+        match closure.free[index].borrow_mut().as_mut() {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(Object::Error(format!(
+                "assignment to undeclared identifier: {}",
+                closure.function.free_names[index]
+            ))),
+        }
+    }
+
+    /// Pops the current frame and pushes its return value for the caller.
+    /// A `return` at the top level instead ends the program, returning false.
+    fn return_from_frame(&mut self, value: Object) -> bool {
+        if self.frames.len() == 1 {
+            self.last_popped = value;
+            return false;
+        }
+        let frame = self.frames.pop().expect("checked above");
+        self.stack.truncate(frame.base_pointer);
+        self.push(value);
+        true
+    }
+
+    /// Decodes the next instruction of the current frame and advances past
+    /// it, or returns `None` once the main frame has run to completion.
+    fn fetch(&mut self) -> Option<(Opcode, [usize; code::MAX_OPERANDS])> {
+        let frame = self.frames.last_mut()?;
+        let instructions = &frame.closure.function.instructions;
+        let byte = *instructions.get(frame.ip)?;
+        let opcode = Opcode::try_from(byte).expect("the compiler only emits valid opcodes");
+        let (operands, read) = code::read_operands(opcode, &instructions[frame.ip + 1..]);
+        frame.ip += 1 + read;
+        Some((opcode, operands))
+    }
+
+    fn call(&mut self, num_arguments: usize) -> Result<(), Object> {
+        let callee_index = self.stack.len() - 1 - num_arguments;
+        match &self.stack[callee_index] {
+            Object::Closure(closure) => {
+                let closure = Rc::clone(closure);
+                if closure.function.num_parameters != num_arguments {
+                    return Err(Object::Error(format!(
+                        "wrong number of arguments: want={}, got={}",
+                        closure.function.num_parameters, num_arguments
+                    )));
+                }
+                if self.frames.len() >= MAX_CALL_DEPTH {
+                    return Err(Object::Error("stack overflow".to_string()));
+                }
+                let arguments = self.stack.split_off(callee_index + 1);
+                self.stack.pop();
+                let num_locals = closure.function.local_names.len();
+                let mut locals: Vec<Variable> = arguments
+                    .into_iter()
+                    .map(|argument| Rc::new(RefCell::new(Some(argument))))
+                    .collect();
+                locals.resize_with(num_locals, Variable::default);
+                self.frames.push(Frame {
+                    closure,
+                    ip: 0,
+                    base_pointer: callee_index,
+                    locals,
+                });
+                Ok(())
+            }
+            Object::Builtin(builtin) => {
+                let function = builtin.function;
+                let arguments = self.stack.split_off(callee_index + 1);
+                self.stack.pop();
+                self.push_result(function(&arguments))
+            }
+            callee => Err(Object::Error(format!(
+                "not a function: {}",
+                callee.type_name()
+            ))),
+        }
+    }
+
+    /// Creates a closure over the variables the function captures, sharing
+    /// them with the current frame rather than copying their values.
+    fn push_closure(&mut self, constant: usize) -> Result<(), Object> {
+        let Object::CompiledFunction(function) = &self.constants[constant] else {
+            return Err(Object::Error(format!(
+                "not a function: {}",
+                self.constants[constant].type_name()
+            )));
+        };
+        let function = Rc::clone(function);
+        let frame = self.current_frame();
+        let free = function
+            .captures
+            .iter()
+            .map(|capture| match *capture {
+                Capture::Local(index) => Rc::clone(&frame.locals[index]),
+                Capture::Free(index) => Rc::clone(&frame.closure.free[index]),
+                Capture::Function => Rc::new(RefCell::new(Some(Object::Closure(Rc::clone(
+                    &frame.closure,
+                ))))),
+            })
+            .collect();
+        self.push(Object::Closure(Rc::new(Closure { function, free })));
+        Ok(())
+    }
+
+    fn current_frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("the main frame is never popped")
+    }
+
+    fn push(&mut self, object: Object) {
+        self.stack.push(object);
+    }
+
+    /// Pushes the result of an operation, stopping the program if it failed.
+    fn push_result(&mut self, result: Object) -> Result<(), Object> {
+        if result.is_error() {
+            return Err(result);
+        }
+        self.push(result);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack
+            .pop()
+            .expect("the compiler keeps the stack balanced")
+    }
+}
+
+/// Reads a variable, which is an error until its `let` has run.
+fn read(variable: &Variable, name: &str) -> Result<Object, Object> {
+    variable
+        .borrow()
+        .clone()
+        .ok_or_else(|| Object::Error(format!("identifier not found: {name}")))
+}
+
+fn infix_operator(opcode: Opcode) -> &'static str {
+    match opcode {
+        Opcode::Add => "+",
+        Opcode::Sub => "-",
+        Opcode::Mul => "*",
+        Opcode::Div => "/",
+        Opcode::Equal => "==",
+        Opcode::NotEqual => "!=",
+        Opcode::GreaterThan => ">",
+        Opcode::GreaterEqual => ">=",
+        Opcode::LessThan => "<",
+        Opcode::LessEqual => "<=",
+        _ => unreachable!("Op{opcode:?} is not an infix operator"),
+    }
+}
+
+fn build_hash(elements: Vec<Object>) -> Result<Object, Object> {
+    let mut pairs = HashMap::with_capacity(elements.len() / 2);
+    let mut elements = elements.into_iter();
+    while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+        let Some(hash_key) = key.hash_key() else {
+            return Err(Object::Error(format!(
+                "unusable as hash key: {}",
+                key.type_name()
+            )));
+        };
+        pairs.insert(hash_key, HashPair { key, value });
+    }
+    Ok(Object::Hash(pairs))
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+    use crate::compiler::Compiler;
+    use crate::object::{Environment, NATIVE_STACK_SIZE};
+    use crate::parser::Parser;
+
+    fn run(input: &str) -> Object {
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        let mut compiler = Compiler::new();
+        compiler.compile(&program).unwrap();
+        Vm::new(compiler.bytecode()).run()
+    }
+
+    /// Runs `input` on both backends and checks that they agree.
+    fn assert_same_as_evaluator(input: &str) -> Object {
+        let mut parser = Parser::new(input);
+        let program = parser.parse_program();
+        let evaluated = evaluator::eval_program(&program, &Environment::new());
+        let result = run(input);
+        assert_eq!(result.to_string(), evaluated.to_string(), "input: {input}");
+        result
+    }
+
+    #[test]
+    fn test_integer_arithmetic() {
+        let test_cases = [
+            ("1", 1),
+            ("1 + 2", 3),
+            ("1 - 2", -1),
+            ("4 / 2", 2),
+            ("50 / 2 * 2 + 10 - 5", 55),
+            ("5 * (2 + 10)", 60),
+            ("-50 + 100 + -50", 0),
+            ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), Object::Integer(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_boolean_expressions() {
+        let test_cases = [
+            ("true", true),
+            ("1 < 2", true),
+            ("1 <= 1", true),
+            ("1 > 2", false),
+            ("2 >= 3", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("true != false", true),
+            ("(1 < 2) == true", true),
+            ("!5", false),
+            ("!!true", true),
+            ("!(if (false) { 5; })", true),
+            (r#""a" < "b""#, true),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), Object::Boolean(expected), "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let test_cases = [
+            ("if (true) { 10 }", Object::Integer(10)),
+            ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20)),
+            ("if (1 > 2) { 10 }", Object::Null),
+            ("if (true) { }", Object::Null),
+            ("if (true) { let a = 1; }", Object::Null),
+            (
+                "if ((if (false) { 10 })) { 10 } else { 20 }",
+                Object::Integer(20),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_global_let_statements() {
+        let test_cases = [
+            ("let one = 1; one", Object::Integer(1)),
+            (
+                "let one = 1; let two = one + one; one + two",
+                Object::Integer(3),
+            ),
+            ("let one = 1;", Object::Null),
+            ("let x = 1; let x = x + 1; x", Object::Integer(2)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_functions() {
+        let test_cases = [
+            ("let f = fn() { 5 + 10; }; f()", Object::Integer(15)),
+            ("let f = fn() { return 99; 100; }; f()", Object::Integer(99)),
+            ("let f = fn() { }; f()", Object::Null),
+            ("let f = fn(a, b) { a + b }; f(1, 2)", Object::Integer(3)),
+            (
+                "let g = 50; let f = fn(a) { let b = a * 2; g - b }; f(10) + f(20)",
+                Object::Integer(40),
+            ),
+            (
+                "let f = fn() { 1 }; let g = fn() { f }; g()()",
+                Object::Integer(1),
+            ),
+            ("return 10; 9", Object::Integer(10)),
+            ("if (true) { return 10; }; 9", Object::Integer(10)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let test_cases = [
+            (
+                "let adder = fn(a) { fn(b) { a + b } }; adder(2)(3)",
+                Object::Integer(5),
+            ),
+            (
+                "let f = fn(a) { fn(b) { fn(c) { a + b + c } } }; f(1)(2)(3)",
+                Object::Integer(6),
+            ),
+            (
+                "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+                Object::Integer(610),
+            ),
+            (
+                "let f = fn() { let countdown = fn(x) { if (x == 0) { 0 } else { countdown(x - 1) } }; countdown(3) }; f()",
+                Object::Integer(0),
+            ),
+            (
+                "let f = fn() { g() }; let g = fn() { 7 }; f()",
+                Object::Integer(7),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_collections_and_builtins() {
+        let test_cases = [
+            (
+                "[1, 2 * 2, 3 + 3]",
+                Object::Array(vec![
+                    Object::Integer(1),
+                    Object::Integer(4),
+                    Object::Integer(6),
+                ]),
+            ),
+            ("[1, 2, 3][1 + 1]", Object::Integer(3)),
+            ("[1][5]", Object::Null),
+            (r#"{"a": 1, 2: 3}[2]"#, Object::Integer(3)),
+            (r#"{}["a"]"#, Object::Null),
+            (r#"len("four")"#, Object::Integer(4)),
+            (
+                "rest(push([1], 2))",
+                Object::Array(vec![Object::Integer(2)]),
+            ),
+            ("let len = 1; len([])", Object::Integer(0)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(run(input), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let test_cases = [
+            ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+            ("-true", "unknown operator: -BOOLEAN"),
+            ("1 / 0", "division by zero"),
+            ("5(1)", "not a function: INTEGER"),
+            ("fn(x) { x }()", "wrong number of arguments: want=1, got=0"),
+            ("len(1)", "argument to `len` not supported, got INTEGER"),
+            ("{[1]: 2}", "unusable as hash key: ARRAY"),
+            ("[1][true]", "array index must be INTEGER, got BOOLEAN"),
+            ("foobar", "identifier not found: foobar"),
+            ("let f = fn() { f() }; f()", "stack overflow"),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(
+                run(input),
+                Object::Error(expected.to_string()),
+                "input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stack_overflow_matches_evaluator() {
+        let depth = MAX_CALL_DEPTH - 1;
+        let inputs = [
+            format!("let c = fn(n) {{ if (n == 0) {{ 0 }} else {{ c(n - 1) }} }}; c({depth})"),
+            format!(
+                "let c = fn(n) {{ if (n == 0) {{ 0 }} else {{ c(n - 1) }} }}; c({})",
+                depth - 1
+            ),
+            "let f = fn(n) { f(n + 1) }; f(0)".to_string(),
+            "let f = fn(n) { 1 + [f(n + 1)][0] }; f(0)".to_string(),
+        ];
+        // The evaluator needs more native stack than a test thread has to
+        // reach the depth limit, just as `main` does.
+        thread::Builder::new()
+            .stack_size(NATIVE_STACK_SIZE)
+            .spawn(move || {
+                let results: Vec<_> = inputs
+                    .iter()
+                    .map(|input| assert_same_as_evaluator(input).to_string())
+                    .collect();
+                assert_eq!(
+                    results,
+                    [
+                        "ERROR: stack overflow",
+                        "0",
+                        "ERROR: stack overflow",
+                        "ERROR: stack overflow"
+                    ]
+                );
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_matches_evaluator() {
+        let inputs = [
+            "let x = 5; x * 2 + 1",
+            "if (false) { 1 }",
+            "let s = \"mon\" + \"key\"; s",
+            "let f = fn(x) { x }; f",
+            "fn(a, b) { a + b }",
+            "[1, \"two\", [true], {1: 2}]",
+            r#"{"b": 2, "a": [1]}"#,
+            "let map = fn(f, xs) { if (len(xs) == 0) { [] } else { push(map(f, rest(xs)), f(first(xs))) } }; map(fn(x) { x * 2 }, [1, 2, 3])",
+            "let counter = fn(n) { if (n > 0) { counter(n - 1) } else { \"done\" } }; counter(20)",
+            "let a = 1; let f = fn() { a }; let a = 2; f()",
+            "if (true) { let y = 3; }",
+            "puts(1); foobar",
+            "9223372036854775807 + 1",
+            "[fn(x) { x }][0] == 1",
+            "let x = 1; x += 2; x *= 3; x",
+            "let x = 1; x = \"one\"; x",
+            "let x = 1; x",
+            "y = 1",
+            "y += 1",
+            "let x = 1; x += true",
+            "let f = fn() { x = 5 }; let x = 1; f(); x",
+            "let f = fn(a) { a -= 1; a }; f(3)",
+            "let g = fn() { let z = 1; z /= 0 }; g()",
+            "let a = [1]; a = push(a, 2); a",
+            // Closures share the variables they capture with the enclosing
+            // function, as the evaluator's environments do.
+            "let g = fn() { let a = 1; let f = fn() { a }; let a = 2; f() }; g()",
+            "let g = fn() { let f = fn() { b }; let b = 2; f() }; g()",
+            "let g = fn() { let f = fn() { b }; f() }; g()",
+            "let g = fn() { let f = fn() { b }; let x = f(); let b = 2; x }; g()",
+            "let g = fn() { if (true) { let f = fn() { fn() { c } } }; let c = 3; f()() }; g()",
+            "let g = fn(n) { let f = fn() { if (n == 0) { 0 } else { n + g(n - 1) } }; f() }; g(4)",
+            "let adders = fn(x) { [fn(y) { x + y }, fn(y) { x * y }] }; adders(3)[1](4)",
+            // Assignments through a capture are seen by every closure.
+            "let c = fn() { let n = 0; fn() { n += 1; n } }; let i = c(); i(); i()",
+            "let c = fn() { let n = 0; fn() { n += 1; n } }; let i = c(); let j = c(); i(); i(); j()",
+            "let g = fn() { let a = 1; let f = fn() { a }; a = 2; f() }; g()",
+            "let g = fn() { let n = 1; let f = fn() { fn() { n *= 10 } }; f()(); n }; g()",
+            "let g = fn() { let f = fn() { f = 5 }; f(); f }; g()",
+            "let f = fn() { f = 5 }; f(); f",
+            "let g = fn() { let f = fn() { b = 1 }; f() }; g()",
+        ];
+        for input in inputs {
+            assert_same_as_evaluator(input);
+        }
+        // Literals may have more operands than the stack starts out with.
+        let elements = vec!["1"; STACK_SIZE + 1].join(", ");
+        let pairs: Vec<String> = (0..=STACK_SIZE).map(|i| format!("{i}: {i}")).collect();
+        let inputs = [
+            format!("len([{elements}])"),
+            format!("[{elements}][{STACK_SIZE}]"),
+            format!("{{{}}}[{STACK_SIZE}]", pairs.join(", ")),
+        ];
+        for input in &inputs {
+            assert!(!assert_same_as_evaluator(input).is_error(), "{input}");
+        }
+    }
+}